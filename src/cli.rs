@@ -0,0 +1,488 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements parsing of command line arguments.
+
+use crate::install::bootloader::Bootloader;
+use std::path::PathBuf;
+
+/// The help message.
+pub const USAGE: &str = "Usage: install [OPTIONS] [COMMAND]
+
+Commands:
+  install [FRONTEND]      Installs the system interactively (default command)
+  auto <ANSWER_FILE>      Installs the system from an answer file, without prompting
+  validate <ANSWER_FILE>  Checks an answer file can be used for installation
+  plan <ANSWER_FILE>      Shows the actions an installation from an answer file would perform
+  list-disks              Lists the disks the system can be installed on
+  list-languages          Lists the available languages
+  repair <ANSWER_FILE>    Reinstalls the bootloader of a system installed from an answer file
+  resume                  Resumes an interrupted installation from the first action that did
+                          not complete, using the state file given with `--state-file`
+
+Frontends:
+  term                    Prompts line by line (default)
+  tui                     Full-screen text interface
+  gui                     Graphical interface on the framebuffer `/dev/fb0`
+  gui-snapshot <DIR>      Graphical interface drawn in memory, each frame being saved in DIR
+
+Options:
+  -h, --help              Prints this help
+  -V, --version           Prints the version of the installer
+      --bootloader <NAME> Bootloader to install: `grub_bios`, `grub_efi`, `hybrid` or `none`
+                          (default: depends on the firmware of the machine)
+      --carry-kernel-args Passes the arguments of the running kernel, such as the console, to
+                          the kernel of the installed system as well
+      --dry-run           Shows the actions the installation would perform instead of
+                          performing them
+      --efi-removable     With UEFI, installs the bootloader at the removable media path
+                          instead of registering a boot entry in the firmware
+      --json              Prints the actions of the installation as JSON. With the `auto`,
+                          `resume` and `repair` commands, prints one JSON object per line for
+                          each progress event or error, instead of text. The interactive
+                          installation shows its progress in its interface regardless
+      --log-file <PATH>   Appends the installation logs to the given file
+      --restore-partitions
+                          If the installation fails, writes back the partition table the
+                          disk had before. The installation cannot be resumed then
+      --sysroot <DIR>     Installs the system in the given directory, without partitioning,
+                          mounting nor installing a bootloader. Only applies to the `auto`,
+                          `plan` and `validate` commands
+      --state-file <PATH> Records the state of the installation in the given file, allowing
+                          to resume it. The file must be on persistent storage, since the
+                          files of the live system are lost on reboot
+      --target-root <DIR> Directory on which the system is mounted during installation
+                          (default: /mnt)
+  -y, --yes               Does not ask for confirmation";
+
+/// The user interface used for an interactive installation.
+pub enum Frontend {
+	/// Line by line prompt on the terminal.
+	Term,
+	/// Full-screen text interface.
+	Tui,
+	/// Graphical interface on the framebuffer device.
+	Gui,
+	/// Graphical interface drawn in memory, each frame being saved in the given directory.
+	GuiSnapshot(PathBuf),
+}
+
+/// A command to be executed by the installer.
+pub enum Command {
+	/// Interactive installation.
+	Install(Frontend),
+	/// Installation from the given answer file.
+	Auto(PathBuf),
+	/// Validation of the given answer file.
+	Validate(PathBuf),
+	/// Shows what an installation from the given answer file would do.
+	Plan(PathBuf),
+	/// Lists available disks.
+	ListDisks,
+	/// Lists available languages.
+	ListLanguages,
+	/// Reinstallation of the bootloader of a system installed from the given answer file.
+	Repair(PathBuf),
+	/// Resumption of an interrupted installation.
+	Resume,
+	/// Prints help.
+	Help,
+	/// Prints the version.
+	Version,
+}
+
+/// Parsed command line arguments.
+pub struct Args {
+	/// The command to execute.
+	pub command: Command,
+
+	/// The file to which installation logs are appended.
+	pub log_file: Option<PathBuf>,
+	/// The directory on which the system is mounted during installation.
+	pub target_root: PathBuf,
+	/// If `true`, the installer does not ask for confirmation.
+	pub yes: bool,
+	/// If `true`, the actions of the installation are shown instead of being performed.
+	pub dry_run: bool,
+	/// If `true`, the actions and progress of the installation are printed as JSON.
+	pub json: bool,
+	/// If set, overrides the bootloader backend.
+	pub bootloader: Option<Bootloader>,
+	/// If `true`, the UEFI bootloader is installed at the removable media path.
+	pub efi_removable: bool,
+	/// If `true`, the arguments of the running kernel are passed to the installed system's.
+	pub carry_kernel_args: bool,
+	/// If `true`, the previous partition table of the disk is restored if the installation
+	/// fails.
+	pub restore_partitions: bool,
+	/// If set, the file in which the state of the installation is recorded.
+	pub state_file: Option<PathBuf>,
+	/// If set, the system is installed in this directory instead of a disk.
+	pub sysroot: Option<PathBuf>,
+}
+
+impl Args {
+	/// Parses the given command line arguments, excluding the program's name.
+	///
+	/// On failure, the function returns an error message.
+	pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+		let mut log_file = None;
+		let mut target_root = PathBuf::from("/mnt");
+		let mut yes = false;
+		let mut dry_run = false;
+		let mut json = false;
+		let mut bootloader = None;
+		let mut efi_removable = false;
+		let mut carry_kernel_args = false;
+		let mut restore_partitions = false;
+		let mut state_file = None;
+		let mut sysroot = None;
+		let mut help = false;
+		let mut version = false;
+		let mut positionals = vec![];
+
+		let mut args = args.into_iter();
+		while let Some(arg) = args.next() {
+			// Options may be given as `--name value` or `--name=value`
+			let (name, inline_value) = match arg.split_once('=') {
+				Some((name, value)) if name.starts_with("--") => (name, Some(value.to_owned())),
+				_ => (arg.as_str(), None),
+			};
+			let mut value = || {
+				inline_value
+					.clone()
+					.or_else(|| args.next())
+					.ok_or_else(|| format!("option `{name}` requires a value"))
+			};
+			// Flags do not take a value, which would otherwise be silently ignored
+			let flag = || match inline_value {
+				Some(_) => Err(format!("option `{name}` does not take a value")),
+				None => Ok(true),
+			};
+
+			match name {
+				"-h" | "--help" => help = flag()?,
+				"-V" | "--version" => version = flag()?,
+				"-y" | "--yes" => yes = flag()?,
+				"--dry-run" => dry_run = flag()?,
+				"--json" => json = flag()?,
+				"--bootloader" => bootloader = Some(value()?.parse()?),
+				"--efi-removable" => efi_removable = flag()?,
+				"--carry-kernel-args" => carry_kernel_args = flag()?,
+				"--restore-partitions" => restore_partitions = flag()?,
+				"--state-file" => state_file = Some(PathBuf::from(value()?)),
+				"--sysroot" => sysroot = Some(PathBuf::from(value()?)),
+				"--log-file" => log_file = Some(PathBuf::from(value()?)),
+				"--target-root" => target_root = PathBuf::from(value()?),
+				_ if name.starts_with('-') && name != "-" => {
+					return Err(format!("unknown option `{name}`"));
+				}
+				_ => positionals.push(arg),
+			}
+		}
+
+		let command = if help {
+			Command::Help
+		} else if version {
+			Command::Version
+		} else {
+			Self::parse_command(positionals)?
+		};
+		let sysroot_command = matches!(
+			command,
+			Command::Auto(_) | Command::Plan(_) | Command::Validate(_)
+		);
+		if sysroot.is_some() && !sysroot_command {
+			return Err("option `--sysroot` cannot be used with this command".to_owned());
+		}
+		if matches!(command, Command::Resume) && state_file.is_none() {
+			return Err("command `resume` requires option `--state-file`".to_owned());
+		}
+		Ok(Self {
+			command,
+
+			log_file,
+			target_root,
+			yes,
+			dry_run,
+			json,
+			bootloader,
+			efi_removable,
+			carry_kernel_args,
+			restore_partitions,
+			state_file,
+			sysroot,
+		})
+	}
+
+	/// Parses the command from the positional arguments.
+	fn parse_command(positionals: Vec<String>) -> Result<Command, String> {
+		let mut positionals = positionals.into_iter();
+		let name = positionals.next();
+		let mut arg = |what: &str| {
+			positionals
+				.next()
+				.ok_or_else(|| format!("missing {what}"))
+				.map(PathBuf::from)
+		};
+
+		let command = match name.as_deref() {
+			None | Some("install") => {
+				let frontend = match arg("frontend").ok() {
+					None => Frontend::Term,
+					Some(f) => match f.to_str() {
+						Some("term") => Frontend::Term,
+						Some("tui") => Frontend::Tui,
+						Some("gui") => Frontend::Gui,
+						Some("gui-snapshot") => Frontend::GuiSnapshot(arg("snapshot directory")?),
+						_ => return Err(format!("invalid frontend `{}`", f.display())),
+					},
+				};
+				Command::Install(frontend)
+			}
+			Some("auto") => Command::Auto(arg("answer file")?),
+			Some("validate") => Command::Validate(arg("answer file")?),
+			Some("plan") => Command::Plan(arg("answer file")?),
+			Some("list-disks") => Command::ListDisks,
+			Some("list-languages") => Command::ListLanguages,
+			Some("repair") => Command::Repair(arg("answer file")?),
+			Some("resume") => Command::Resume,
+			Some(name) => return Err(format!("unknown command `{name}`")),
+		};
+
+		if let Some(extra) = positionals.next() {
+			return Err(format!("unexpected argument `{extra}`"));
+		}
+		Ok(command)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Parses the given arguments.
+	fn parse(args: &[&str]) -> Result<Args, String> {
+		Args::parse(args.iter().map(|a| a.to_string()))
+	}
+
+	/// Parses the given arguments, which must be valid.
+	fn parse_ok(args: &[&str]) -> Args {
+		parse(args).unwrap_or_else(|e| panic!("cannot parse {args:?}: {e}"))
+	}
+
+	#[test]
+	fn default_command() {
+		let args = parse_ok(&[]);
+		assert!(matches!(args.command, Command::Install(Frontend::Term)));
+		assert_eq!(args.target_root, PathBuf::from("/mnt"));
+		assert_eq!(args.state_file, None);
+		assert!(!args.yes && !args.dry_run && !args.json);
+	}
+
+	#[test]
+	fn frontends() {
+		assert!(matches!(
+			parse_ok(&["install"]).command,
+			Command::Install(Frontend::Term)
+		));
+		assert!(matches!(
+			parse_ok(&["install", "tui"]).command,
+			Command::Install(Frontend::Tui)
+		));
+		assert!(matches!(
+			parse_ok(&["install", "gui"]).command,
+			Command::Install(Frontend::Gui)
+		));
+		let Command::Install(Frontend::GuiSnapshot(dir)) =
+			parse_ok(&["install", "gui-snapshot", "frames"]).command
+		else {
+			panic!("expected the gui-snapshot frontend");
+		};
+		assert_eq!(dir, PathBuf::from("frames"));
+
+		assert_eq!(
+			parse(&["install", "gui-snapshot"]).err().as_deref(),
+			Some("missing snapshot directory")
+		);
+		assert_eq!(
+			parse(&["install", "foo"]).err().as_deref(),
+			Some("invalid frontend `foo`")
+		);
+		// Frontends are not commands
+		assert_eq!(
+			parse(&["gui-snapshot", "frames"]).err().as_deref(),
+			Some("unknown command `gui-snapshot`")
+		);
+	}
+
+	#[test]
+	fn commands() {
+		let Command::Auto(path) = parse_ok(&["auto", "answers.json"]).command else {
+			panic!("expected the auto command");
+		};
+		assert_eq!(path, PathBuf::from("answers.json"));
+		assert!(matches!(
+			parse_ok(&["validate", "a.json"]).command,
+			Command::Validate(_)
+		));
+		assert!(matches!(
+			parse_ok(&["plan", "a.json"]).command,
+			Command::Plan(_)
+		));
+		assert!(matches!(
+			parse_ok(&["repair", "a.json"]).command,
+			Command::Repair(_)
+		));
+		assert!(matches!(
+			parse_ok(&["list-disks"]).command,
+			Command::ListDisks
+		));
+		assert!(matches!(
+			parse_ok(&["list-languages"]).command,
+			Command::ListLanguages
+		));
+		assert!(matches!(
+			parse_ok(&["resume", "--state-file", "state.json"]).command,
+			Command::Resume
+		));
+		// The state file is not recorded anywhere by default
+		assert_eq!(
+			parse(&["resume"]).err().as_deref(),
+			Some("command `resume` requires option `--state-file`")
+		);
+		assert_eq!(
+			parse(&["foo"]).err().as_deref(),
+			Some("unknown command `foo`")
+		);
+	}
+
+	#[test]
+	fn positionals() {
+		assert_eq!(
+			parse(&["auto"]).err().as_deref(),
+			Some("missing answer file")
+		);
+		assert_eq!(
+			parse(&["auto", "a.json", "b.json"]).err().as_deref(),
+			Some("unexpected argument `b.json`")
+		);
+		assert_eq!(
+			parse(&["list-disks", "sda"]).err().as_deref(),
+			Some("unexpected argument `sda`")
+		);
+		// A lone dash is an argument, not an option
+		let Command::Auto(path) = parse_ok(&["auto", "-"]).command else {
+			panic!("expected the auto command");
+		};
+		assert_eq!(path, PathBuf::from("-"));
+	}
+
+	#[test]
+	fn option_values() {
+		let args = parse_ok(&["--log-file", "a.log", "--target-root=/target", "install"]);
+		assert_eq!(args.log_file, Some(PathBuf::from("a.log")));
+		assert_eq!(args.target_root, PathBuf::from("/target"));
+		let args = parse_ok(&["resume", "--state-file=state.json"]);
+		assert_eq!(args.state_file, Some(PathBuf::from("state.json")));
+		// The value is split at the first `=` only
+		let args = parse_ok(&["--log-file=a=b.log"]);
+		assert_eq!(args.log_file, Some(PathBuf::from("a=b.log")));
+
+		assert_eq!(
+			parse(&["install", "--log-file"]).err().as_deref(),
+			Some("option `--log-file` requires a value")
+		);
+		assert_eq!(parse(&["-x"]).err().as_deref(), Some("unknown option `-x`"));
+		assert_eq!(
+			parse(&["--foo=bar"]).err().as_deref(),
+			Some("unknown option `--foo`")
+		);
+	}
+
+	#[test]
+	fn flags() {
+		let args = parse_ok(&[
+			"-y",
+			"--dry-run",
+			"--json",
+			"--efi-removable",
+			"--carry-kernel-args",
+			"--restore-partitions",
+			"auto",
+			"a.json",
+		]);
+		assert!(args.yes && args.dry_run && args.json);
+		assert!(args.efi_removable && args.carry_kernel_args && args.restore_partitions);
+		assert!(matches!(parse_ok(&["-h"]).command, Command::Help));
+		assert!(matches!(parse_ok(&["--version"]).command, Command::Version));
+		// Help takes precedence over an invalid command
+		assert!(matches!(
+			parse_ok(&["foo", "--help"]).command,
+			Command::Help
+		));
+		assert_eq!(
+			parse(&["--yes=no"]).err().as_deref(),
+			Some("option `--yes` does not take a value")
+		);
+		assert_eq!(
+			parse(&["--dry-run=", "auto", "a.json"]).err().as_deref(),
+			Some("option `--dry-run` does not take a value")
+		);
+	}
+
+	#[test]
+	fn bootloader() {
+		let args = parse_ok(&["--bootloader", "grub_efi"]);
+		assert_eq!(args.bootloader, Some(Bootloader::GrubEfi));
+		let args = parse_ok(&["--bootloader=none"]);
+		assert_eq!(args.bootloader, Some(Bootloader::None));
+		assert!(parse(&["--bootloader", "lilo"]).is_err());
+	}
+
+	#[test]
+	fn sysroot() {
+		for command in [
+			&["auto", "a.json"][..],
+			&["plan", "a.json"],
+			&["validate", "a.json"],
+		] {
+			let args: Vec<&str> = ["--sysroot", "/srv/maestro"]
+				.iter()
+				.chain(command)
+				.copied()
+				.collect();
+			assert_eq!(parse_ok(&args).sysroot, Some(PathBuf::from("/srv/maestro")));
+		}
+		for command in [
+			&["install"][..],
+			&["resume", "--state-file=state.json"],
+			&["repair", "a.json"],
+		] {
+			let args: Vec<&str> = ["--sysroot=/srv/maestro"]
+				.iter()
+				.chain(command)
+				.copied()
+				.collect();
+			assert_eq!(
+				parse(&args).err().as_deref(),
+				Some("option `--sysroot` cannot be used with this command")
+			);
+		}
+	}
+}
@@ -0,0 +1,314 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Detection of the hardware of the machine the system is installed on.
+//!
+//! Information is read from `/proc`, `/sys` and the `uname` command. Information that cannot be
+//! read is left empty instead of failing, since it is used only to pick defaults and warn the
+//! user.
+
+use common::maestro_utils::util::ByteSize;
+use std::{
+	fmt, fs,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+/// The architectures the system can be installed on.
+const SUPPORTED_ARCHS: &[&str] = &["x86", "x86_64"];
+/// The minimum amount of memory required to run the system, in bytes.
+const MIN_RAM: u64 = 256 * 1024 * 1024;
+/// One gibibyte, in bytes.
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// The firmware of the machine.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Firmware {
+	/// Legacy BIOS, or UEFI in compatibility mode.
+	Bios,
+	/// UEFI.
+	Uefi,
+}
+
+impl fmt::Display for Firmware {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Bios => write!(fmt, "BIOS"),
+			Self::Uefi => write!(fmt, "UEFI"),
+		}
+	}
+}
+
+/// A disk of the machine.
+pub struct DiskInfo {
+	/// The path to the device file of the disk.
+	pub path: PathBuf,
+	/// The model name of the disk.
+	pub model: String,
+	/// The serial number of the disk.
+	pub serial: String,
+	/// The size of the disk in bytes.
+	pub size: u64,
+	/// Tells whether the disk is rotational, as opposed to solid state.
+	pub rotational: bool,
+	/// Tells whether the disk is removable, such as a USB stick.
+	pub removable: bool,
+}
+
+impl fmt::Display for DiskInfo {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let model = if self.model.is_empty() {
+			"unknown model"
+		} else {
+			&self.model
+		};
+		write!(
+			fmt,
+			"{}: {model}, {}",
+			self.path.display(),
+			ByteSize(self.size)
+		)?;
+		if !self.serial.is_empty() {
+			write!(fmt, ", serial: {}", self.serial)?;
+		}
+		write!(fmt, ", {}", if self.rotational { "HDD" } else { "SSD" })?;
+		if self.removable {
+			write!(fmt, ", removable")?;
+		}
+		Ok(())
+	}
+}
+
+/// An input device of the machine.
+pub struct InputDevice {
+	/// The name of the device.
+	pub name: String,
+	/// Tells whether the device is a keyboard.
+	pub keyboard: bool,
+}
+
+/// The hardware of the machine.
+pub struct Hardware {
+	/// The firmware.
+	pub firmware: Firmware,
+	/// The CPU architecture. If it could not be detected, the string is empty.
+	pub arch: String,
+	/// The CPU model name.
+	pub cpu_model: String,
+	/// The amount of memory in bytes.
+	pub ram: u64,
+	/// The disks. If they could not be listed, the value is `None`.
+	pub disks: Option<Vec<DiskInfo>>,
+	/// The input devices. If they could not be listed, the value is `None`.
+	pub input_devices: Option<Vec<InputDevice>>,
+}
+
+/// Reads the file at the given path and returns its content, trimmed.
+///
+/// If the file cannot be read, the function returns an empty string.
+fn read_attr(path: &Path) -> String {
+	fs::read_to_string(path)
+		.map(|s| s.trim().to_owned())
+		.unwrap_or_default()
+}
+
+/// Returns the value of the first field named `name` in the file at `path`, where each line is
+/// made of a name and a value separated by a colon.
+fn read_field(path: &str, name: &str) -> Option<String> {
+	fs::read_to_string(path).ok()?.lines().find_map(|line| {
+		let (n, value) = line.split_once(':')?;
+		(n.trim() == name).then(|| value.trim().to_owned())
+	})
+}
+
+/// Returns the architecture of the running system.
+///
+/// The architecture is the one reported by the kernel, which may differ from the one the installer
+/// has been compiled for, such as an `x86` installer running on an `x86_64` system.
+fn detect_arch() -> String {
+	let arch = Command::new("uname")
+		.arg("-m")
+		.output()
+		.ok()
+		.filter(|output| output.status.success())
+		.map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+		.unwrap_or_default();
+	match arch.as_str() {
+		"i386" | "i486" | "i586" | "i686" => "x86".to_owned(),
+		_ => arch,
+	}
+}
+
+/// Returns the list of disks, ignoring virtual block devices such as loop devices.
+///
+/// If the disks cannot be listed, the function returns `None`.
+fn detect_disks() -> Option<Vec<DiskInfo>> {
+	let entries = fs::read_dir("/sys/block").ok()?;
+	let mut disks: Vec<DiskInfo> = entries
+		.filter_map(Result::ok)
+		.map(|e| e.path())
+		// Virtual devices are not backed by a device
+		.filter(|path| path.join("device").exists())
+		.map(|sys_path| {
+			let name = sys_path.file_name().unwrap();
+			let mut serial = read_attr(&sys_path.join("device/serial"));
+			if serial.is_empty() {
+				serial = read_attr(&sys_path.join("serial"));
+			}
+			DiskInfo {
+				path: Path::new("/dev").join(name),
+				model: read_attr(&sys_path.join("device/model")),
+				serial,
+				size: read_attr(&sys_path.join("size"))
+					.parse::<u64>()
+					.unwrap_or(0) * 512,
+				rotational: read_attr(&sys_path.join("queue/rotational")) == "1",
+				removable: read_attr(&sys_path.join("removable")) == "1",
+			}
+		})
+		.collect();
+	disks.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+	Some(disks)
+}
+
+/// Returns the list of input devices.
+///
+/// If the devices cannot be listed, the function returns `None`.
+fn detect_input_devices() -> Option<Vec<InputDevice>> {
+	let content = fs::read_to_string("/proc/bus/input/devices").ok()?;
+	// Devices are separated by empty lines
+	let devices = content
+		.split("\n\n")
+		.filter_map(|dev| {
+			let mut name = None;
+			let mut keyboard = false;
+			for line in dev.lines() {
+				if let Some(n) = line.strip_prefix("N: Name=") {
+					name = Some(n.trim_matches('"').to_owned());
+				} else if let Some(handlers) = line.strip_prefix("H: Handlers=") {
+					keyboard = handlers.split_whitespace().any(|h| h == "kbd");
+				}
+			}
+			Some(InputDevice {
+				name: name?,
+				keyboard,
+			})
+		})
+		.collect();
+	Some(devices)
+}
+
+impl Hardware {
+	/// Inspects the hardware of the machine.
+	pub fn detect() -> Self {
+		let firmware = if Path::new("/sys/firmware/efi").exists() {
+			Firmware::Uefi
+		} else {
+			Firmware::Bios
+		};
+		let ram = read_field("/proc/meminfo", "MemTotal")
+			.and_then(|s| s.trim_end_matches("kB").trim().parse::<u64>().ok())
+			.unwrap_or(0)
+			* 1024;
+		Self {
+			firmware,
+			arch: detect_arch(),
+			cpu_model: read_field("/proc/cpuinfo", "model name").unwrap_or_default(),
+			ram,
+			disks: detect_disks(),
+			input_devices: detect_input_devices(),
+		}
+	}
+
+	/// Returns the information about the disk with the given device file, if detected.
+	pub fn disk(&self, path: &Path) -> Option<&DiskInfo> {
+		self.disks.iter().flatten().find(|d| d.path == path)
+	}
+
+	/// Returns the recommended size of swap space in bytes, according to the amount of memory.
+	pub fn swap_size(&self) -> u64 {
+		match self.ram {
+			// The amount of memory could not be read
+			0 => 2 * GIB,
+			// Small amounts of memory are doubled
+			ram if ram <= 2 * GIB => 2 * ram,
+			ram => ram.min(8 * GIB),
+		}
+	}
+
+	/// Returns the lines of a report describing the hardware.
+	pub fn report(&self) -> Vec<String> {
+		let cpu_model = if self.cpu_model.is_empty() {
+			"unknown model"
+		} else {
+			&self.cpu_model
+		};
+		let arch = if self.arch.is_empty() {
+			"unknown architecture"
+		} else {
+			&self.arch
+		};
+		let mut lines = vec![
+			format!("Firmware: {}", self.firmware),
+			format!("CPU: {cpu_model} ({arch})"),
+			format!("Memory: {}", ByteSize(self.ram)),
+			"Disks:".to_owned(),
+		];
+		match &self.disks {
+			Some(disks) => lines.extend(disks.iter().map(|d| format!("  - {d}"))),
+			None => lines.push("  Cannot be listed".to_owned()),
+		}
+		lines.push("Input devices:".to_owned());
+		match &self.input_devices {
+			Some(devices) => lines.extend(devices.iter().map(|d| format!("  - {}", d.name))),
+			None => lines.push("  Cannot be listed".to_owned()),
+		}
+		lines
+	}
+
+	/// Returns warnings about configurations the system does not support, or might not work
+	/// properly on.
+	pub fn warnings(&self) -> Vec<String> {
+		let mut warnings = vec![];
+		// An empty architecture means it could not be detected
+		if !self.arch.is_empty() && !SUPPORTED_ARCHS.contains(&self.arch.as_str()) {
+			warnings.push(format!("The `{}` architecture is not supported", self.arch));
+		}
+		// A RAM size of zero means it could not be read
+		if self.ram != 0 && self.ram < MIN_RAM {
+			warnings.push(format!(
+				"The machine has less than {} of memory",
+				ByteSize(MIN_RAM)
+			));
+		}
+		// Devices that could not be listed are not reported missing
+		if let Some(disks) = &self.disks {
+			if disks.is_empty() {
+				warnings.push("No disk has been detected".to_owned());
+			} else if disks.iter().all(|d| d.removable) {
+				warnings.push("Only removable disks have been detected".to_owned());
+			}
+		}
+		if let Some(devices) = &self.input_devices {
+			if !devices.iter().any(|d| d.keyboard) {
+				warnings.push("No keyboard has been detected".to_owned());
+			}
+		}
+		warnings
+	}
+}
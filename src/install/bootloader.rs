@@ -0,0 +1,168 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The bootloader installed along with the system is chosen among several backends, each
+//! requiring its own partitions.
+
+use super::progress::InstallProgress;
+use crate::hw::Firmware;
+use common::util::current_arch;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, path::Path, process::Command, str::FromStr};
+
+/// The type of BIOS boot partitions, in which GRUB embeds itself on GPT disks.
+pub const BIOS_BOOT_TYPE: &str = "21686148-6449-6E6F-744E-656564454649";
+/// The type of EFI System partitions.
+pub const ESP_TYPE: &str = "C12A7328-F81F-11D2-BA4B-00A0C93EC93B";
+
+/// The identifier of the system in the EFI boot entries.
+const EFI_BOOTLOADER_ID: &str = "maestro";
+
+/// A bootloader backend.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bootloader {
+	/// GRUB, booting with a BIOS.
+	GrubBios,
+	/// GRUB, booting with UEFI.
+	GrubEfi,
+	/// GRUB, booting with either a BIOS or UEFI, for disks moved between machines.
+	Hybrid,
+	/// No bootloader is installed, the machine being booted by an external bootloader or from the
+	/// network.
+	None,
+}
+
+impl Bootloader {
+	/// All backends, in the order they are presented to the user.
+	pub const ALL: [Self; 4] = [Self::GrubBios, Self::GrubEfi, Self::Hybrid, Self::None];
+
+	/// Returns the backend to use by default on a machine with the given firmware.
+	pub fn default_for(firmware: Firmware) -> Self {
+		match firmware {
+			Firmware::Bios => Self::GrubBios,
+			Firmware::Uefi => Self::GrubEfi,
+		}
+	}
+
+	/// Returns the name of the backend, as written in answer files.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::GrubBios => "grub_bios",
+			Self::GrubEfi => "grub_efi",
+			Self::Hybrid => "hybrid",
+			Self::None => "none",
+		}
+	}
+
+	/// Tells whether the backend boots with a BIOS.
+	pub fn is_bios(&self) -> bool {
+		matches!(self, Self::GrubBios | Self::Hybrid)
+	}
+
+	/// Tells whether the backend boots with UEFI.
+	pub fn is_efi(&self) -> bool {
+		matches!(self, Self::GrubEfi | Self::Hybrid)
+	}
+
+	/// Returns the types of the partitions the backend requires on the disk.
+	pub fn required_partitions(&self) -> Vec<&'static str> {
+		let mut types = vec![];
+		if self.is_bios() {
+			types.push(BIOS_BOOT_TYPE);
+		}
+		if self.is_efi() {
+			types.push(ESP_TYPE);
+		}
+		types
+	}
+
+	/// Installs the bootloader.
+	///
+	/// Arguments:
+	/// - `disk` is the path to the device file of the disk the system is installed on.
+	/// - `boot_dir` is the path to the mountpoint of the system's `/boot` directory.
+	/// - `efi_dir` is the path to the mountpoint of the EFI System partition. It is required if
+	///   the backend boots with UEFI.
+	/// - `removable` tells whether the bootloader is installed at the removable media path of the
+	///   EFI System partition instead of registering a boot entry.
+	pub fn install(
+		&self,
+		disk: &Path,
+		boot_dir: &Path,
+		efi_dir: Option<&Path>,
+		removable: bool,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		if self.is_bios() {
+			let mut cmd = Command::new("grub-install");
+			cmd.arg("--target=i386-pc")
+				.arg(format!("--boot-directory={}", boot_dir.display()))
+				.arg(disk);
+			grub_install(&mut cmd, progress)?;
+		}
+		if self.is_efi() {
+			let efi_dir = efi_dir.ok_or("no EFI System partition is mounted")?;
+			let target = match current_arch() {
+				"x86_64" => "x86_64-efi",
+				_ => "i386-efi",
+			};
+			let mut cmd = Command::new("grub-install");
+			cmd.arg(format!("--target={target}"))
+				.arg(format!("--boot-directory={}", boot_dir.display()))
+				.arg(format!("--efi-directory={}", efi_dir.display()))
+				.arg(format!("--bootloader-id={EFI_BOOTLOADER_ID}"));
+			if removable {
+				cmd.arg("--removable");
+			}
+			grub_install(&mut cmd, progress)?;
+		}
+		Ok(())
+	}
+}
+
+/// Runs the given `grub-install` command.
+fn grub_install(cmd: &mut Command, progress: &mut InstallProgress) -> Result<(), Box<dyn Error>> {
+	let output = progress.run(cmd)?;
+	if !output.status.success() {
+		return Err("Cannot install bootloader".into());
+	}
+	Ok(())
+}
+
+impl FromStr for Bootloader {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.into_iter()
+			.find(|b| b.name() == s)
+			.ok_or_else(|| format!("invalid bootloader `{s}`"))
+	}
+}
+
+impl fmt::Display for Bootloader {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::GrubBios => write!(fmt, "GRUB (BIOS)"),
+			Self::GrubEfi => write!(fmt, "GRUB (UEFI)"),
+			Self::Hybrid => write!(fmt, "GRUB (BIOS and UEFI)"),
+			Self::None => write!(fmt, "None"),
+		}
+	}
+}
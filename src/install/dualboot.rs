@@ -0,0 +1,349 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Detection of the other operating systems installed on the machine, so that the boot menu of
+//! the installed system can chainload them.
+
+use super::{
+	bootloader::ESP_TYPE,
+	grub,
+	grub::{Config, MenuEntry},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	fmt, fs,
+	fs::File,
+	io::Read,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+/// The directory on which EFI System partitions are mounted to look for loaders.
+const PROBE_DIR: &str = "/tmp/maestro_install_probe";
+/// The type of EFI System partitions in an MBR partition table.
+const MBR_ESP_TYPE: &str = "0xef";
+
+/// Known EFI loaders, relative to the directory of their vendor in the `EFI` directory.
+const EFI_LOADERS: &[&str] = &[
+	"Boot/bootmgfw.efi",
+	"shimx64.efi",
+	"grubx64.efi",
+	"shimia32.efi",
+	"grubia32.efi",
+];
+
+/// An operating system installed on another partition.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct OtherOs {
+	/// The name of the system, shown in the boot menu.
+	pub name: String,
+	/// The UUID of the filesystem containing the loader of the system.
+	pub fs_uuid: String,
+	/// The type of this filesystem, as reported by `blkid`.
+	pub fs_type: String,
+	/// The path to the EFI loader of the system, relative to the root of the filesystem.
+	///
+	/// If None, the system is booted with a BIOS by chainloading the boot sector of the
+	/// partition.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub efi_loader: Option<PathBuf>,
+	/// If set, the boot sector of the disk containing the filesystem is chainloaded instead of
+	/// the one of the partition, such as for bootloaders installed in the MBR.
+	#[serde(default)]
+	pub disk_boot_sector: bool,
+}
+
+impl OtherOs {
+	/// Checks the information about the system can be written in the configuration of the
+	/// bootloader.
+	///
+	/// The name and the path to the loader are quoted in the configuration, but they must not
+	/// contain control characters such as newlines.
+	pub fn check(&self) -> Result<(), String> {
+		if self.name.is_empty() || self.name.contains(char::is_control) {
+			return Err(format!(
+				"invalid system name `{}`",
+				self.name.escape_debug()
+			));
+		}
+		let uuid_valid = self
+			.fs_uuid
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '-');
+		if self.fs_uuid.is_empty() || !uuid_valid {
+			return Err(format!(
+				"invalid filesystem UUID `{}` for system `{}`",
+				self.fs_uuid.escape_debug(),
+				self.name
+			));
+		}
+		if let Some(loader) = &self.efi_loader {
+			let loader = loader.to_string_lossy();
+			if !loader.starts_with('/') || loader.contains(char::is_control) {
+				return Err(format!(
+					"invalid EFI loader `{}` for system `{}`",
+					loader.escape_debug(),
+					self.name
+				));
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns the entry of the boot menu chainloading the system.
+	///
+	/// The entry is shown only when booting with the same firmware as the system.
+	pub fn menu_entry(&self) -> MenuEntry {
+		let (platform, loader) = match &self.efi_loader {
+			Some(loader) => ("efi", grub::quote(&loader.display().to_string())),
+			// `root` names the partition, such as `hd1,gpt2`. The disk is the part before the
+			// comma
+			None if self.disk_boot_sector => ("pc", "(${disk})+1".to_owned()),
+			None => ("pc", "+1".to_owned()),
+		};
+		let mut commands = vec![
+			"insmod part_gpt".to_owned(),
+			"insmod part_msdos".to_owned(),
+			"insmod chain".to_owned(),
+		];
+		if let Some(module) = Config::fs_module(&self.fs_type) {
+			commands.push(format!("insmod {module}"));
+		}
+		commands.push(format!(
+			"search --no-floppy --fs-uuid --set=root {}",
+			self.fs_uuid
+		));
+		if self.disk_boot_sector {
+			commands.push("insmod regexp".to_owned());
+			commands.push("regexp --set=1:disk '^([^,]+)' \"$root\"".to_owned());
+		}
+		commands.push(format!("chainloader {loader}"));
+		MenuEntry {
+			title: self.name.clone(),
+			condition: Some(format!("[ \"${{grub_platform}}\" = \"{platform}\" ]")),
+			commands,
+		}
+	}
+}
+
+impl fmt::Display for OtherOs {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.efi_loader {
+			Some(loader) => write!(fmt, "{} (UEFI, `{}`)", self.name, loader.display()),
+			None => write!(fmt, "{} (BIOS)", self.name),
+		}
+	}
+}
+
+/// Returns the name of the disk the given partition is on.
+///
+/// If the device is not a partition, the function returns None.
+fn parent_disk(dev: &Path) -> Option<String> {
+	let sys_path = Path::new("/sys/class/block").join(dev.file_name()?);
+	if !sys_path.join("partition").exists() {
+		return None;
+	}
+	let sys_path = fs::canonicalize(sys_path).ok()?;
+	Some(
+		sys_path
+			.parent()?
+			.file_name()?
+			.to_string_lossy()
+			.into_owned(),
+	)
+}
+
+/// Tells whether the given partition is an EFI System partition, from the type in the partition
+/// table.
+fn is_esp(dev: &Path) -> bool {
+	let Ok(output) = Command::new("blkid")
+		.args(["-p", "-s", "PART_ENTRY_TYPE", "-o", "value"])
+		.arg(dev)
+		.output()
+	else {
+		return false;
+	};
+	let part_type = String::from_utf8_lossy(&output.stdout);
+	let part_type = part_type.trim();
+	part_type.eq_ignore_ascii_case(ESP_TYPE) || part_type.eq_ignore_ascii_case(MBR_ESP_TYPE)
+}
+
+/// Returns the EFI loaders found in the EFI System partition on the given device, along with the
+/// name of the system they boot.
+///
+/// The partition is mounted read-only while looking for loaders. If it cannot be unmounted
+/// afterwards, the function returns an error, the loaders found being lost.
+fn efi_loaders(dev: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+	if fs::create_dir_all(PROBE_DIR).is_err() {
+		return Ok(vec![]);
+	}
+	let mounted = Command::new("mount")
+		.args(["-t", "vfat", "-o", "ro"])
+		.arg(dev)
+		.arg(PROBE_DIR)
+		.output()
+		.is_ok_and(|output| output.status.success());
+	if !mounted {
+		let _ = fs::remove_dir(PROBE_DIR);
+		return Ok(vec![]);
+	}
+
+	let mut loaders = vec![];
+	let vendors = fs::read_dir(Path::new(PROBE_DIR).join("EFI"));
+	for vendor in vendors.into_iter().flatten().flatten() {
+		let vendor = vendor.file_name().to_string_lossy().into_owned();
+		// The removable media path is a copy of another loader
+		if vendor.eq_ignore_ascii_case("boot") {
+			continue;
+		}
+		let vendor_path = Path::new("/EFI").join(&vendor);
+		let loader = EFI_LOADERS.iter().find(|loader| {
+			common::util::concat_paths(Path::new(PROBE_DIR), &vendor_path.join(loader)).is_file()
+		});
+		if let Some(loader) = loader {
+			let name = match vendor.as_str() {
+				"Microsoft" => "Windows Boot Manager".to_owned(),
+				_ => vendor.clone(),
+			};
+			loaders.push((name, vendor_path.join(loader)));
+		}
+	}
+
+	let output = Command::new("umount")
+		.arg(PROBE_DIR)
+		.output()
+		.map_err(|e| e.to_string())
+		.and_then(|output| {
+			if output.status.success() {
+				Ok(())
+			} else {
+				Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+			}
+		});
+	if let Err(e) = output {
+		return Err(format!(
+			"Cannot unmount `{}` from `{PROBE_DIR}`: {e}",
+			dev.display()
+		));
+	}
+	let _ = fs::remove_dir(PROBE_DIR);
+	Ok(loaders)
+}
+
+/// Returns the name of the system whose loader is in the boot sector of the given device, if
+/// any.
+fn boot_sector_os(dev: &Path) -> Option<&'static str> {
+	let mut sector = [0u8; 512];
+	File::open(dev).ok()?.read_exact(&mut sector).ok()?;
+	if sector[510..] != [0x55, 0xaa] {
+		return None;
+	}
+	let contains = |s: &[u8]| sector.windows(s.len()).any(|w| w == s);
+	if contains(b"BOOTMGR") || contains(b"NTLDR") {
+		Some("Windows")
+	} else if contains(b"GRUB") {
+		Some("GRUB")
+	} else {
+		None
+	}
+}
+
+/// Scans the disks of the machine for other operating systems, ignoring the disk at
+/// `exclude_disk`, on which the system is installed.
+///
+/// The boot sectors of disks and partitions are read, and EFI System partitions are mounted
+/// read-only while scanning them.
+///
+/// Along with the systems found, the function returns warnings about what could not be scanned.
+pub fn scan(exclude_disk: &Path) -> (Vec<OtherOs>, Vec<String>) {
+	let Ok(output) = Command::new("blkid").arg("-o").arg("export").output() else {
+		return (vec![], vec![]);
+	};
+	let output = String::from_utf8_lossy(&output.stdout);
+	let exclude_disk = exclude_disk
+		.file_name()
+		.map(|name| name.to_string_lossy().into_owned());
+
+	let mut systems = vec![];
+	let mut warnings = vec![];
+	// For each disk, the first filesystem on it, allowing GRUB to find the disk at boot
+	let mut disks: BTreeMap<String, (&str, &str)> = BTreeMap::new();
+	// EFI System partitions are not probed anymore if one could not be unmounted
+	let mut probe_esp = true;
+	// Devices are separated by empty lines
+	for dev in output.split("\n\n") {
+		let field = |name: &str| {
+			dev.lines()
+				.find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+		};
+		let (Some(dev_path), Some(fs_uuid), Some(fs_type)) =
+			(field("DEVNAME"), field("UUID"), field("TYPE"))
+		else {
+			continue;
+		};
+		let dev_path = Path::new(dev_path);
+		let Some(disk) = parent_disk(dev_path) else {
+			continue;
+		};
+		if Some(&disk) == exclude_disk.as_ref() {
+			continue;
+		}
+		disks.entry(disk).or_insert((fs_uuid, fs_type));
+
+		let new = |name: String, efi_loader: Option<PathBuf>| OtherOs {
+			name,
+			fs_uuid: fs_uuid.to_owned(),
+			fs_type: fs_type.to_owned(),
+			efi_loader,
+			disk_boot_sector: false,
+		};
+		if probe_esp && fs_type == "vfat" && is_esp(dev_path) {
+			match efi_loaders(dev_path) {
+				Ok(loaders) => systems.extend(
+					loaders
+						.into_iter()
+						.map(|(name, loader)| new(name, Some(loader))),
+				),
+				Err(e) => {
+					warnings.push(e);
+					probe_esp = false;
+				}
+			}
+		}
+		if let Some(name) = boot_sector_os(dev_path) {
+			systems.push(new(format!("{name} on {}", dev_path.display()), None));
+		}
+	}
+	// Bootloaders booting with a BIOS are usually installed in the boot sector of the disk
+	for (disk, (fs_uuid, fs_type)) in disks {
+		let disk_path = Path::new("/dev").join(disk);
+		if let Some(name) = boot_sector_os(&disk_path) {
+			systems.push(OtherOs {
+				name: format!("{name} on {}", disk_path.display()),
+				fs_uuid: fs_uuid.to_owned(),
+				fs_type: fs_type.to_owned(),
+				efi_loader: None,
+				disk_boot_sector: true,
+			});
+		}
+	}
+	// Names of EFI vendors come from the filesystem and may not fit in the boot menu
+	systems.retain(|system| system.check().is_ok());
+	(systems, warnings)
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Each partition holds a filesystem, which determines how it is created and mounted.
+
+use super::bootloader::{BIOS_BOOT_TYPE, ESP_TYPE};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path, process::Command};
+
+/// The type of Linux swap partitions.
+pub const SWAP_TYPE: &str = "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F";
+
+/// A filesystem to be created on a partition.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filesystem {
+	/// The second extended filesystem.
+	Ext2,
+	/// The fourth extended filesystem.
+	Ext4,
+	/// FAT32, required for EFI System partitions.
+	Fat32,
+	/// Swap space.
+	Swap,
+	/// The partition is left unformatted.
+	None,
+}
+
+impl Filesystem {
+	/// All filesystems, in the order they are presented to the user.
+	pub const ALL: [Self; 5] = [Self::Ext2, Self::Ext4, Self::Fat32, Self::Swap, Self::None];
+
+	/// Returns the filesystem used by default for partitions of the given type.
+	pub fn default_for(part_type: &str) -> Self {
+		let part_type = part_type.to_uppercase();
+		match part_type.as_str() {
+			BIOS_BOOT_TYPE => Self::None,
+			ESP_TYPE => Self::Fat32,
+			SWAP_TYPE => Self::Swap,
+			// The kernel of the installed system cannot mount ext4 yet, only ext2
+			_ => Self::Ext2,
+		}
+	}
+
+	/// Returns the command creating the filesystem on the given device.
+	///
+	/// If the partition is left unformatted, the function returns None.
+	pub fn mkfs(&self, device: &Path) -> Option<Command> {
+		let mut cmd = match self {
+			Self::Ext2 => Command::new("mkfs.ext2"),
+			Self::Ext4 => Command::new("mkfs.ext4"),
+			Self::Fat32 => {
+				let mut cmd = Command::new("mkfs.fat");
+				cmd.arg("-F").arg("32");
+				cmd
+			}
+			Self::Swap => Command::new("mkswap"),
+			Self::None => return None,
+		};
+		cmd.arg(device);
+		Some(cmd)
+	}
+
+	/// Returns the type to pass to `mount` to mount the filesystem.
+	///
+	/// If the filesystem cannot be mounted, the function returns None.
+	pub fn mount_type(&self) -> Option<&'static str> {
+		match self {
+			Self::Ext2 => Some("ext2"),
+			Self::Ext4 => Some("ext4"),
+			Self::Fat32 => Some("vfat"),
+			Self::Swap | Self::None => None,
+		}
+	}
+
+	/// Returns the name of the GRUB module reading the filesystem, if any.
+	pub fn grub_module(&self) -> Option<&'static str> {
+		match self {
+			// The `ext2` module reads ext4 as well
+			Self::Ext2 | Self::Ext4 => Some("ext2"),
+			Self::Fat32 => Some("fat"),
+			Self::Swap | Self::None => None,
+		}
+	}
+}
+
+impl fmt::Display for Filesystem {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Ext2 => write!(fmt, "ext2"),
+			Self::Ext4 => write!(fmt, "ext4"),
+			Self::Fat32 => write!(fmt, "FAT32"),
+			Self::Swap => write!(fmt, "swap"),
+			Self::None => write!(fmt, "none"),
+		}
+	}
+}
@@ -0,0 +1,419 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Generation of the GRUB configuration of the installed system, from the partitions created
+//! during installation.
+
+use super::dualboot::OtherOs;
+use serde::{Deserialize, Serialize};
+use std::{
+	fmt, fs,
+	path::{Path, PathBuf},
+};
+
+/// The default number of seconds the boot menu is shown before booting the default entry.
+pub const DEFAULT_TIMEOUT: u32 = 5;
+
+/// The suffix of the previous kernel, next to the current one.
+///
+/// The installer keeps a copy of the kernel it installs there. Since upgrades replace only the
+/// current kernel, the copy remains bootable if an upgrade breaks the boot.
+const PREVIOUS_KERNEL_SUFFIX: &str = ".old";
+/// The argument added to the command line of the kernel by the recovery entry.
+const RECOVERY_ARG: &str = "single";
+
+/// The beginning of the configuration, setting how the menu looks and behaves.
+///
+/// `@TIMEOUT@` is replaced with the menu timeout.
+const HEADER: &str = include_str!("grub.cfg");
+
+/// The settings from which the configuration is generated, decided when planning the
+/// installation.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Settings {
+	/// The number of the partition mounted at `/`.
+	pub root_partition: usize,
+	/// The number of the partition containing the kernel.
+	pub boot_partition: usize,
+	/// The path to the kernel, relative to the root of its partition.
+	pub kernel: PathBuf,
+	/// The number of seconds the menu is shown before booting the first entry.
+	pub timeout: u32,
+	/// Additional arguments passed to the kernel.
+	#[serde(default)]
+	pub kernel_args: Vec<String>,
+	/// Other operating systems to chainload.
+	#[serde(default)]
+	pub other_systems: Vec<OtherOs>,
+}
+
+impl Settings {
+	/// Returns the entries of the boot menu, given the UUID of the root partition.
+	///
+	/// The root partition is given to the kernel by its UUID since device numbers differ between
+	/// machines, depending on the type of disk.
+	///
+	/// Besides the entry booting the system normally, the menu has an entry booting the previous
+	/// kernel if there is one (see [`PREVIOUS_KERNEL_SUFFIX`]), and a recovery entry booting the
+	/// kernel with [`RECOVERY_ARG`].
+	pub fn menu_entries(&self, root_uuid: &str) -> Vec<MenuEntry> {
+		let boot = |kernel: &Path, extra_arg: Option<&str>| {
+			let mut cmd = format!("multiboot2 {} -root PARTUUID={root_uuid}", kernel.display());
+			for arg in self.kernel_args.iter().map(String::as_str).chain(extra_arg) {
+				cmd.push(' ');
+				cmd.push_str(arg);
+			}
+			cmd
+		};
+		let previous_kernel = previous_kernel(&self.kernel);
+
+		let mut entries = vec![
+			MenuEntry {
+				title: "Maestro".to_owned(),
+				condition: None,
+				commands: vec![boot(&self.kernel, None)],
+			},
+			MenuEntry {
+				title: "Maestro (previous kernel)".to_owned(),
+				// The previous kernel exists only after an upgrade
+				condition: Some(format!("[ -f {} ]", previous_kernel.display())),
+				commands: vec![boot(&previous_kernel, None)],
+			},
+			MenuEntry {
+				title: "Maestro (recovery)".to_owned(),
+				condition: None,
+				commands: vec![boot(&self.kernel, Some(RECOVERY_ARG))],
+			},
+		];
+		entries.extend(self.other_systems.iter().map(OtherOs::menu_entry));
+		entries
+	}
+}
+
+/// Returns the path to the previous kernel, given the path to the current one.
+pub fn previous_kernel(kernel: &Path) -> PathBuf {
+	let mut path = kernel.as_os_str().to_owned();
+	path.push(PREVIOUS_KERNEL_SUFFIX);
+	PathBuf::from(path)
+}
+
+/// Returns the given string as a double-quoted word of the configuration.
+///
+/// The characters which GRUB interprets inside double quotes are escaped, so that the string is
+/// used as is.
+pub fn quote(s: &str) -> String {
+	let mut quoted = String::with_capacity(s.len() + 2);
+	quoted.push('"');
+	for c in s.chars() {
+		if matches!(c, '"' | '$' | '\\') {
+			quoted.push('\\');
+		}
+		quoted.push(c);
+	}
+	quoted.push('"');
+	quoted
+}
+
+/// Checks the given kernel argument can be written as is in the configuration.
+///
+/// Characters having a meaning in the configuration's syntax are rejected, since the argument
+/// would change the commands of the entry instead of being passed to the kernel.
+pub fn check_kernel_arg(arg: &str) -> Result<(), String> {
+	if arg.is_empty() {
+		return Err("empty kernel argument".to_owned());
+	}
+	let invalid = |c: char| c.is_whitespace() || "\"'\\$;&|<>{}`#".contains(c);
+	if let Some(c) = arg.chars().find(|c| invalid(*c)) {
+		return Err(format!(
+			"invalid character `{c}` in kernel argument `{arg}`"
+		));
+	}
+	Ok(())
+}
+
+/// Returns the arguments of the running kernel worth carrying over to the installed system,
+/// such as the console or debug flags.
+///
+/// The arguments set by the installer itself are left out, as well as those which cannot be
+/// written in the configuration.
+pub fn live_kernel_args() -> Vec<String> {
+	let Ok(cmdline) = fs::read_to_string("/proc/cmdline") else {
+		return vec![];
+	};
+	let mut args = vec![];
+	let mut iter = cmdline.split_whitespace();
+	while let Some(arg) = iter.next() {
+		match arg {
+			// The root device differs between the installation medium and the installed system. It
+			// is given either by UUID or by major and minor numbers
+			"-root" => {
+				if !iter.next().is_some_and(|dev| dev.starts_with("PARTUUID=")) {
+					iter.next();
+				}
+			}
+			_ if arg.starts_with("BOOT_IMAGE=") => {}
+			_ if check_kernel_arg(arg).is_err() => {}
+			_ => args.push(arg.to_owned()),
+		}
+	}
+	args
+}
+
+/// An entry of the boot menu.
+pub struct MenuEntry {
+	/// The title shown in the menu.
+	pub title: String,
+	/// If set, the entry is in the menu only if this GRUB condition is true at boot.
+	pub condition: Option<String>,
+	/// The commands executed when the entry is selected.
+	pub commands: Vec<String>,
+}
+
+/// A GRUB configuration file.
+pub struct Config {
+	/// The number of seconds the menu is shown before booting the first entry.
+	pub timeout: u32,
+	/// The GRUB modules required to read the filesystem containing the kernel.
+	pub modules: Vec<&'static str>,
+	/// The UUID of the filesystem containing the kernel.
+	pub boot_uuid: String,
+	/// The entries of the menu.
+	pub entries: Vec<MenuEntry>,
+}
+
+impl Config {
+	/// Returns the name of the GRUB module reading the given filesystem, as named by `blkid`.
+	///
+	/// If the filesystem is not known, the function returns None.
+	pub fn fs_module(filesystem: &str) -> Option<&'static str> {
+		match filesystem {
+			"vfat" => Some("fat"),
+			// The `ext2` module reads ext3 and ext4 as well
+			"ext2" | "ext3" | "ext4" => Some("ext2"),
+			"ntfs" => Some("ntfs"),
+			"exfat" => Some("exfat"),
+			"btrfs" => Some("btrfs"),
+			"xfs" => Some("xfs"),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for Config {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			fmt,
+			"{}",
+			HEADER.replace("@TIMEOUT@", &self.timeout.to_string())
+		)?;
+		writeln!(fmt)?;
+		for module in &self.modules {
+			writeln!(fmt, "insmod {module}")?;
+		}
+		writeln!(
+			fmt,
+			"search --no-floppy --fs-uuid --set=root {}",
+			self.boot_uuid
+		)?;
+		for entry in &self.entries {
+			writeln!(fmt)?;
+			let indent = match &entry.condition {
+				Some(condition) => {
+					writeln!(fmt, "if {condition}; then")?;
+					"\t"
+				}
+				None => "",
+			};
+			writeln!(fmt, "{indent}menuentry {} {{", quote(&entry.title))?;
+			for cmd in &entry.commands {
+				writeln!(fmt, "{indent}\t{cmd}")?;
+			}
+			writeln!(fmt, "{indent}}}")?;
+			if entry.condition.is_some() {
+				writeln!(fmt, "fi")?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The UUID of the root partition used in tests.
+	const ROOT_UUID: &str = "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0";
+
+	/// Returns settings booting the kernel at `/maestro` with the given arguments.
+	fn settings(kernel_args: &[&str]) -> Settings {
+		Settings {
+			root_partition: 2,
+			boot_partition: 2,
+			kernel: PathBuf::from("/maestro"),
+			timeout: DEFAULT_TIMEOUT,
+			kernel_args: kernel_args.iter().map(|arg| arg.to_string()).collect(),
+			other_systems: vec![],
+		}
+	}
+
+	#[test]
+	fn menu_entries() {
+		let entries = settings(&["console=ttyS0"]).menu_entries(ROOT_UUID);
+		assert_eq!(entries.len(), 3);
+
+		assert_eq!(entries[0].title, "Maestro");
+		assert!(entries[0].condition.is_none());
+		assert_eq!(
+			entries[0].commands,
+			[format!(
+				"multiboot2 /maestro -root PARTUUID={ROOT_UUID} console=ttyS0"
+			)]
+		);
+
+		// The previous kernel is booted only if it exists
+		assert_eq!(entries[1].condition.as_deref(), Some("[ -f /maestro.old ]"));
+		assert_eq!(
+			entries[1].commands,
+			[format!(
+				"multiboot2 /maestro.old -root PARTUUID={ROOT_UUID} console=ttyS0"
+			)]
+		);
+
+		// The recovery argument comes after the user's arguments
+		assert!(entries[2].condition.is_none());
+		assert_eq!(
+			entries[2].commands,
+			[format!(
+				"multiboot2 /maestro -root PARTUUID={ROOT_UUID} console=ttyS0 {RECOVERY_ARG}"
+			)]
+		);
+	}
+
+	#[test]
+	fn menu_entries_other_systems() {
+		let mut settings = settings(&[]);
+		settings.other_systems.push(OtherOs {
+			name: "Windows Boot Manager".to_owned(),
+			fs_uuid: "1A2B-3C4D".to_owned(),
+			fs_type: "vfat".to_owned(),
+			efi_loader: Some(PathBuf::from("/EFI/Microsoft/Boot/bootmgfw.efi")),
+			disk_boot_sector: false,
+		});
+		settings.other_systems.push(OtherOs {
+			name: "GRUB on /dev/sdb".to_owned(),
+			fs_uuid: "5e6f7a8b".to_owned(),
+			fs_type: "ext4".to_owned(),
+			efi_loader: None,
+			disk_boot_sector: true,
+		});
+		let entries = settings.menu_entries(ROOT_UUID);
+		assert_eq!(entries.len(), 5);
+		let entry = &entries[3];
+		assert_eq!(entry.title, "Windows Boot Manager");
+		// Chainloading an EFI loader requires booting with UEFI
+		assert_eq!(
+			entry.condition.as_deref(),
+			Some("[ \"${grub_platform}\" = \"efi\" ]")
+		);
+		assert_eq!(
+			entry.commands.last().map(String::as_str),
+			Some("chainloader \"/EFI/Microsoft/Boot/bootmgfw.efi\"")
+		);
+		// The boot sector of the disk is found from a filesystem on it
+		let entry = &entries[4];
+		assert_eq!(
+			entry.condition.as_deref(),
+			Some("[ \"${grub_platform}\" = \"pc\" ]")
+		);
+		assert_eq!(
+			entry.commands[entry.commands.len() - 4..],
+			[
+				"search --no-floppy --fs-uuid --set=root 5e6f7a8b",
+				"insmod regexp",
+				"regexp --set=1:disk '^([^,]+)' \"$root\"",
+				"chainloader (${disk})+1",
+			]
+		);
+	}
+
+	#[test]
+	fn config() {
+		let config = Config {
+			timeout: 3,
+			modules: vec!["part_gpt", "ext2"],
+			boot_uuid: "1234".to_owned(),
+			entries: settings(&[]).menu_entries(ROOT_UUID),
+		};
+		let config = config.to_string();
+
+		assert!(config.contains("\nset default=0\n"));
+		assert!(config.contains("\nset timeout=3\n"));
+		assert!(!config.contains("@TIMEOUT@"));
+		assert!(config.contains("\nset fallback=1\n"));
+		assert!(config.contains("\ninsmod part_gpt\ninsmod ext2\n"));
+		assert!(config.contains("\nsearch --no-floppy --fs-uuid --set=root 1234\n"));
+		let kernel = format!("/maestro -root PARTUUID={ROOT_UUID}");
+		assert!(config.contains(&format!(
+			"\nmenuentry \"Maestro\" {{\n\tmultiboot2 {kernel}\n}}\n"
+		)));
+		// Conditional entries are wrapped in an `if` block
+		assert!(config.contains(&format!(
+			"\nif [ -f /maestro.old ]; then\n\tmenuentry \"Maestro (previous kernel)\" {{\n\t\tmultiboot2 /maestro.old -root PARTUUID={ROOT_UUID}\n\t}}\nfi\n"
+		)));
+		// Entries are in the order of the menu, which the fallback relies on
+		let default = config.find("menuentry \"Maestro\"").unwrap();
+		let previous = config
+			.find("menuentry \"Maestro (previous kernel)\"")
+			.unwrap();
+		let recovery = config.find("menuentry \"Maestro (recovery)\"").unwrap();
+		assert!(default < previous && previous < recovery);
+	}
+
+	#[test]
+	fn quote_escapes() {
+		assert_eq!(quote("Maestro"), "\"Maestro\"");
+		assert_eq!(quote("a \"b\" $c \\d"), "\"a \\\"b\\\" \\$c \\\\d\"");
+	}
+
+	#[test]
+	fn kernel_args() {
+		assert!(check_kernel_arg("console=ttyS0,115200").is_ok());
+		assert!(check_kernel_arg("debug").is_ok());
+		for arg in [
+			"", "a b", "a\"b", "a;b", "$x", "a\\b", "a}b", "a`b`", "#", "#a", "a#b",
+		] {
+			assert!(check_kernel_arg(arg).is_err(), "`{arg}` is accepted");
+		}
+	}
+
+	#[test]
+	fn menu_entry_title() {
+		let config = Config {
+			timeout: DEFAULT_TIMEOUT,
+			modules: vec![],
+			boot_uuid: "1234".to_owned(),
+			entries: vec![MenuEntry {
+				title: "\"; reboot; echo \"".to_owned(),
+				condition: None,
+				commands: vec![],
+			}],
+		};
+		let config = config.to_string();
+		assert!(config.contains("menuentry \"\\\"; reboot; echo \\\"\" {"));
+	}
+}
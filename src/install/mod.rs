@@ -18,7 +18,17 @@
 
 //! This module handles the installation procedure.
 
-use crate::lang::Language;
+pub mod bootloader;
+pub mod dualboot;
+pub mod filesystem;
+pub mod grub;
+pub mod plan;
+pub mod progress;
+pub mod rollback;
+pub mod state;
+
+use crate::{hw::Hardware, lang::Language};
+use bootloader::{Bootloader, BIOS_BOOT_TYPE, ESP_TYPE};
 use common::{
 	fhs,
 	maestro_utils::{
@@ -31,19 +41,47 @@ use common::{
 	util::current_arch,
 	Environment,
 };
+use filesystem::{Filesystem, SWAP_TYPE};
+use plan::Action;
+use progress::{Event, InstallProgress};
+use rollback::{Rollback, Undo};
 use serde::{Deserialize, Serialize};
+use state::InstallState;
 use std::{
 	error::Error,
 	fmt, fs,
-	fs::{OpenOptions, Permissions},
-	io::Write,
-	os::unix::{fs::chown, prelude::PermissionsExt},
+	fs::{File, OpenOptions, Permissions},
+	io,
+	io::{BufReader, Write},
+	os::unix::{
+		fs::{chown, OpenOptionsExt},
+		prelude::PermissionsExt,
+	},
 	path::{Path, PathBuf},
 	process::Command,
 	str::FromStr,
+	thread,
+	time::{Duration, Instant},
 };
 use uuid::Uuid;
-// TODO Use InstallProgress instead of printing directly
+
+/// The size of a sector in bytes.
+pub const SECTOR_SIZE: u64 = 512;
+
+/// The maximum duration to wait for the device files of new partitions.
+const PARTITIONS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The path to the installation logs, in the installed system.
+const LOG_PATH: &str = "var/log/maestro-install.log";
+
+/// The path to the swap file, in the installed system.
+const SWAP_FILE_PATH: &str = "swapfile";
+
+/// The path to the local repository containing the packages to install.
+const LOCAL_REPO: &str = "/local_repo";
+
+/// The name of the kernel file, in `/boot`.
+const KERNEL_NAME: &str = "maestro";
 
 /// Structure representing a partition to be created.
 #[derive(Clone, Deserialize, Serialize)]
@@ -63,6 +101,153 @@ pub struct PartitionDesc {
 	///
 	/// If None, the partition shouldn't be mounted.
 	pub mount_path: Option<PathBuf>,
+	/// The filesystem to create on the partition.
+	///
+	/// If None, the default filesystem for the partition's type is used.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub filesystem: Option<Filesystem>,
+}
+
+impl PartitionDesc {
+	/// Returns the default partition layout for a disk of `disk_size` sectors, on the given
+	/// hardware, with the partitions required by the given bootloader.
+	///
+	/// The layout uses the whole disk. If `swap_size` is not zero, a swap partition of this size
+	/// in sectors is created, within the limit of half of the space left for the system.
+	///
+	/// If the disk is too small to hold the layout, the function returns an error.
+	pub fn auto_layout(
+		disk_size: u64,
+		hw: &Hardware,
+		bootloader: Bootloader,
+		swap_size: u64,
+	) -> Result<Vec<Self>, Box<dyn Error>> {
+		let mut parts = vec![];
+		let mut start = 2048;
+		if bootloader.is_bios() {
+			parts.push(PartitionDesc {
+				start,
+				size: 2048,
+
+				part_type: BIOS_BOOT_TYPE.to_owned(),
+
+				bootable: false,
+
+				mount_path: None,
+				filesystem: None,
+			});
+			start += 2048;
+		}
+		if bootloader.is_efi() {
+			parts.push(PartitionDesc {
+				start,
+				size: 262144,
+
+				part_type: ESP_TYPE.to_owned(),
+
+				bootable: true,
+
+				mount_path: Some("/boot/efi".into()),
+				filesystem: None,
+			});
+			start += 262144;
+		}
+
+		// The space left for the system
+		let available = disk_size
+			.checked_sub(start)
+			.filter(|size| *size > 0)
+			.ok_or_else(|| format!("the disk is too small ({disk_size} sectors)"))?;
+		let swap_size = swap_size.min(available / 2) / 2048 * 2048;
+		if swap_size > 0 {
+			parts.push(PartitionDesc {
+				start,
+				size: swap_size,
+
+				part_type: SWAP_TYPE.to_owned(),
+
+				bootable: false,
+
+				mount_path: None,
+				filesystem: None,
+			});
+			start += swap_size;
+		}
+
+		let root_part = PartitionDesc {
+			start,
+			size: available - swap_size,
+
+			part_type: match hw.arch.as_str() {
+				// Linux root (x86-64)
+				"x86_64" => "4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709",
+				// Linux root (x86)
+				_ => "44479540-F297-41B2-9AF7-D131D5F0458A",
+			}
+			.to_owned(),
+
+			bootable: false,
+
+			mount_path: Some("/".into()),
+			filesystem: None,
+		};
+
+		parts.push(root_part);
+		Ok(parts)
+	}
+
+	/// Returns the default size in sectors of the swap partition for a disk of `disk_size`
+	/// sectors.
+	pub fn default_swap_size(disk_size: u64, hw: &Hardware) -> u64 {
+		(hw.swap_size() / SECTOR_SIZE).min(disk_size / 4)
+	}
+
+	/// Tells whether the partition is an EFI System partition.
+	pub fn is_esp(&self) -> bool {
+		self.part_type.eq_ignore_ascii_case(ESP_TYPE)
+	}
+
+	/// Returns the filesystem to create on the partition.
+	pub fn get_filesystem(&self) -> Filesystem {
+		self.filesystem
+			.unwrap_or_else(|| Filesystem::default_for(&self.part_type))
+	}
+
+	/// Checks the filesystem of the partition can be used with its type and mount path.
+	pub fn check_filesystem(&self) -> Result<(), String> {
+		let filesystem = self.get_filesystem();
+		if self.mount_path.is_some() && filesystem.mount_type().is_none() {
+			return Err(format!("filesystem {filesystem} cannot be mounted"));
+		}
+		// The firmware reads the EFI System partition, which therefore has to be FAT
+		if self.is_esp() && filesystem != Filesystem::Fat32 {
+			return Err("the EFI System partition must be FAT32".to_owned());
+		}
+		Ok(())
+	}
+
+	/// Returns a human-readable name for the partition's type.
+	///
+	/// If the type is not known, the function returns it as is.
+	pub fn type_name(&self) -> &str {
+		partition_type_name(&self.part_type)
+	}
+}
+
+/// Returns a human-readable name for the given partition type.
+///
+/// If the type is not known, the function returns it as is.
+pub fn partition_type_name(part_type: &str) -> &str {
+	match part_type.to_uppercase().as_str() {
+		BIOS_BOOT_TYPE => "BIOS boot",
+		ESP_TYPE => "EFI System",
+		"BC13C2FF-59E6-4262-A352-B275FD6F7172" => "Linux extended boot",
+		"44479540-F297-41B2-9AF7-D131D5F0458A" => "Linux root (x86)",
+		"4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709" => "Linux root (x86-64)",
+		"0FC63DAF-8483-4772-8E79-3D69D8477DE4" => "Linux filesystem",
+		SWAP_TYPE => "Linux swap",
+		_ => part_type,
+	}
 }
 
 impl fmt::Display for PartitionDesc {
@@ -71,6 +256,7 @@ impl fmt::Display for PartitionDesc {
 		if self.bootable {
 			write!(fmt, ", bootable")?;
 		}
+		write!(fmt, ", filesystem: {}", self.get_filesystem())?;
 		if let Some(mount_path) = &self.mount_path {
 			write!(fmt, ", mount path: {} ", mount_path.display())?;
 		}
@@ -79,7 +265,11 @@ impl fmt::Display for PartitionDesc {
 }
 
 /// Structure storing installation information.
+///
+/// The structure can be serialized to an answer file, allowing to perform an unattended
+/// installation.
 #[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct InstallInfo {
 	/// The system's language.
 	pub lang: Option<Language>,
@@ -94,155 +284,549 @@ pub struct InstallInfo {
 	/// Admin username.
 	pub admin_user: String,
 	/// Hashed admin password.
+	#[serde(skip_serializing_if = "String::is_empty")]
 	pub admin_pass: String,
 
 	/// The path to the disk on which the system is to be installed.
+	///
+	/// This can also be a disk image file.
 	pub selected_disk: PathBuf,
+	/// If set, `selected_disk` is a disk image file of the given size in bytes, created if it
+	/// does not exist.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub image_size: Option<u64>,
 	/// The partition scheme to be used.
 	pub partitions: Vec<PartitionDesc>,
+	/// If set, a swap file of the given size in bytes is created on the root filesystem, as an
+	/// alternative to a swap partition.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub swap_file_size: Option<u64>,
+
+	/// The bootloader backend.
+	///
+	/// If None, the default backend for the firmware of the machine performing the installation
+	/// is used.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub bootloader: Option<Bootloader>,
+	/// With UEFI, tells whether the bootloader is installed at the removable media path instead
+	/// of registering a boot entry in the firmware.
+	///
+	/// This is required for disks meant to boot on another machine.
+	pub efi_removable: bool,
+	/// The number of seconds the boot menu is shown before booting the system.
+	///
+	/// If None, [`grub::DEFAULT_TIMEOUT`] is used.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub boot_timeout: Option<u32>,
+	/// Additional arguments passed to the kernel of the installed system.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub kernel_args: Vec<String>,
+	/// Other operating systems the boot menu of the installed system allows to boot.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub other_systems: Vec<dualboot::OtherOs>,
 }
 
 impl InstallInfo {
-	/// Creates partitions on the disk.
-	fn partition_disks(&self) -> Result<(), Box<dyn Error>> {
-		println!(
-			"Create partition table on `{}`...",
-			self.selected_disk.display()
-		);
+	/// Loads installation information from the answer file at the given path.
+	pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let file = File::open(path)?;
+		let reader = BufReader::new(file);
+		Ok(serde_json::from_reader(reader)?)
+	}
 
-		let partitions = self
-			.partitions
-			.iter()
-			.map(|desc| {
-				// TODO handle error
-				let part_type = PartitionType::from_str(desc.part_type.as_str()).unwrap();
-				Partition {
-					start: desc.start,
-					size: desc.size,
+	/// Saves the installation information to an answer file at the given path.
+	///
+	/// If `with_pass` is `false`, the admin password hash is not written to the file.
+	pub fn save(&self, path: &Path, with_pass: bool) -> Result<(), Box<dyn Error>> {
+		let mut infos = self.clone();
+		if !with_pass {
+			infos.admin_pass.clear();
+		}
 
-					part_type,
+		let file = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.mode(0o600)
+			.open(path)?;
+		// The mode is applied only if the file is created
+		file.set_permissions(Permissions::from_mode(0o600))?;
+		serde_json::to_writer_pretty(file, &infos)?;
+		Ok(())
+	}
 
-					uuid: Some(Uuid::new_v4()),
+	/// Checks the information about the installed system itself (language, hostname and users) is
+	/// complete.
+	///
+	/// This is the only information required to install the system in a directory.
+	pub fn validate_system(&self) -> Result<(), Box<dyn Error>> {
+		if self.lang.is_none() {
+			return Err("no language selected".into());
+		}
+		if self.hostname.is_empty() {
+			return Err("no hostname specified".into());
+		}
+		check_hostname(&self.hostname)?;
+		if self.admin_user.is_empty() {
+			return Err("no admin username specified".into());
+		}
+		if self.admin_pass.is_empty() {
+			return Err("no admin password hash specified".into());
+		}
+		Ok(())
+	}
 
-					bootable: desc.bootable,
-				}
-			})
-			.collect();
-		let partition_table = PartitionTable {
-			table_type: PartitionTableType::Gpt,
-			partitions,
+	/// Checks the installation information is complete and consistent.
+	///
+	/// This function must be called before performing an installation from information that has
+	/// not been prompted to the user, such as an answer file.
+	pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+		self.validate_system()?;
+
+		// Check disk
+		let disk_size = if self.is_image() {
+			let size = match self.image_size {
+				Some(size) => size,
+				None => fs::metadata(&self.selected_disk)?.len(),
+			};
+			size / SECTOR_SIZE
+		} else {
+			if !Disk::list()?.contains(&self.selected_disk) {
+				return Err(
+					format!("disk `{}` doesn't exist", self.selected_disk.display()).into(),
+				);
+			}
+			let Some(disk) = Disk::read(self.selected_disk.clone())? else {
+				return Err(format!("cannot read disk `{}`", self.selected_disk.display()).into());
+			};
+			disk.size()
 		};
 
-		let mut disk = Disk::read(self.selected_disk.clone())?.unwrap();
-		disk.partition_table = partition_table;
-		disk.write()?;
-		disk::read_partitions(&self.selected_disk)?;
+		// Check partitions
+		if !self
+			.partitions
+			.iter()
+			.any(|p| p.mount_path.as_deref() == Some(Path::new("/")))
+		{
+			return Err("no partition is mounted at `/`".into());
+		}
+		for (i, part) in self.partitions.iter().enumerate() {
+			if PartitionType::from_str(&part.part_type).is_err() {
+				return Err(
+					format!("partition {}: invalid type `{}`", i + 1, part.part_type).into(),
+				);
+			}
+			let end = part.start.checked_add(part.size);
+			if part.size == 0 || end.is_none_or(|end| end > disk_size) {
+				return Err(format!("partition {}: out of the disk's bounds", i + 1).into());
+			}
+			let overlaps = self.partitions[..i]
+				.iter()
+				.any(|p| part.start < p.start + p.size && p.start < part.start + part.size);
+			if overlaps {
+				return Err(format!("partition {}: overlaps another partition", i + 1).into());
+			}
+			part.check_filesystem()
+				.map_err(|e| format!("partition {}: {e}", i + 1))?;
+		}
+		if let Some(size) = self.swap_file_size {
+			if size == 0 {
+				return Err("the size of the swap file must not be zero".into());
+			}
+			let swap_partition = self
+				.partitions
+				.iter()
+				.any(|p| p.get_filesystem() == Filesystem::Swap);
+			if swap_partition {
+				return Err("a swap file cannot be used along with a swap partition".into());
+			}
+		}
+		for arg in &self.kernel_args {
+			grub::check_kernel_arg(arg)?;
+		}
+		for system in &self.other_systems {
+			system.check()?;
+		}
+		let bootloader = self.get_bootloader();
+		for part_type in bootloader.required_partitions() {
+			let part = self
+				.partitions
+				.iter()
+				.find(|p| p.part_type.eq_ignore_ascii_case(part_type));
+			let Some(part) = part else {
+				return Err(format!(
+					"bootloader `{}` requires a {} partition",
+					bootloader.name(),
+					partition_type_name(part_type)
+				)
+				.into());
+			};
+			if part.is_esp() && part.mount_path.is_none() {
+				return Err("the EFI System partition must be mounted".into());
+			}
+		}
 
 		Ok(())
 	}
 
-	/// Creates a filesystem on each partition.
-	fn create_filesystems(&self) -> Result<(), Box<dyn Error>> {
-		for (i, part) in self.partitions.iter().enumerate() {
-			if part.mount_path.is_none() {
-				continue;
-			}
+	/// Returns the bootloader backend.
+	pub fn get_bootloader(&self) -> Bootloader {
+		self.bootloader
+			.unwrap_or_else(|| Bootloader::default_for(Hardware::detect().firmware))
+	}
 
-			// TODO support nvme
-			let dev_path = format!("{}{}", self.selected_disk.display(), i + 1);
+	/// Returns the path at which the EFI System partition is mounted in the installed system,
+	/// if any.
+	fn esp_mount_path(&self) -> Option<&Path> {
+		self.partitions
+			.iter()
+			.filter(|p| p.is_esp())
+			.find_map(|p| p.mount_path.as_deref())
+	}
 
-			println!("Create filesystem on `{dev_path}`");
+	/// Tells whether the system is installed in a disk image file instead of a block device.
+	pub fn is_image(&self) -> bool {
+		self.image_size.is_some() || self.selected_disk.is_file()
+	}
 
-			// TODO use ext4
-			let status = Command::new("mkfs.ext2").arg(dev_path).status()?;
-			if !status.success() {
-				return Err("Filesystem creation failed!".into());
-			}
+	/// Returns the actions preparing the environment in which the system is installed: the
+	/// directory on which it is mounted and, if installing in an image, the loop device.
+	fn setup_actions(&self, mnt_path: &Path) -> Vec<Action> {
+		let mut actions = vec![Action::CreateDir {
+			path: mnt_path.to_owned(),
+		}];
+		if self.is_image() {
+			actions.push(Action::AttachImage {
+				image: self.selected_disk.clone(),
+				size: self.image_size,
+			});
 		}
-		Ok(())
+		actions
+	}
+
+	/// Returns the actions releasing the environment in which the system is installed.
+	fn teardown_actions(&self, mnt_path: &Path) -> Vec<Action> {
+		let mut actions = vec![Action::Unmount {
+			path: mnt_path.to_owned(),
+		}];
+		if self.is_image() {
+			actions.push(Action::DetachImage {
+				image: self.selected_disk.clone(),
+			});
+		}
+		actions
 	}
 
-	/// Mounts filesystems to install the system on them.
-	fn mount_filesystems(&self) -> Result<(), Box<dyn Error>> {
+	/// Returns the actions mounting filesystems under `mnt_path`.
+	fn mount_actions(&self, mnt_path: &Path) -> Vec<Action> {
 		// Ensure partitions are mount in the right order
 		let mut parts: Vec<(usize, &PartitionDesc)> = self.partitions.iter().enumerate().collect();
 		parts.sort_unstable_by(|(_, a), (_, b)| a.mount_path.cmp(&b.mount_path));
 
-		for (i, part) in parts {
-			let Some(mnt_path) = &part.mount_path else {
-				continue;
-			};
+		parts
+			.into_iter()
+			.filter_map(|(i, part)| {
+				let part_mnt_path = part.mount_path.as_ref()?;
+				Some(Action::Mount {
+					partition: i + 1,
+					path: common::util::concat_paths(mnt_path, part_mnt_path),
+				})
+			})
+			.collect()
+	}
+
+	/// Returns the actions installing the bootloader and writing its configuration.
+	fn bootloader_actions(&self, mnt_path: &Path) -> Result<Vec<Action>, Box<dyn Error>> {
+		let bootloader = self.get_bootloader();
+		if bootloader == Bootloader::None {
+			return Ok(vec![]);
+		}
+		let efi_dir = if bootloader.is_efi() {
+			let path = self
+				.esp_mount_path()
+				.ok_or("the EFI System partition must be mounted")?;
+			Some(common::util::concat_paths(mnt_path, path))
+		} else {
+			None
+		};
+		let install = Action::InstallBootloader {
+			bootloader,
+			disk: self.selected_disk.clone(),
+			boot_dir: mnt_path.join("boot"),
+			// Boot entries are stored in the firmware of the machine performing the installation,
+			// which is not the one an image boots on
+			removable: efi_dir.is_some() && (self.efi_removable || self.is_image()),
+			efi_dir,
+		};
+
+		let mounted = || {
+			self.partitions
+				.iter()
+				.enumerate()
+				.filter_map(|(i, p)| Some((i + 1, p.mount_path.as_deref()?)))
+		};
+		let root_partition = mounted()
+			.find(|(_, path)| *path == Path::new("/"))
+			.map(|(i, _)| i)
+			.ok_or("no partition is mounted at `/`")?;
+		// The kernel is in `/boot`, which may be on its own partition
+		let boot_dir = Path::new("/boot");
+		let (boot_partition, boot_mount_path) = mounted()
+			.filter(|(_, path)| boot_dir.starts_with(path))
+			.max_by_key(|(_, path)| path.components().count())
+			// The root partition always matches
+			.unwrap();
+		let kernel = Path::new("/")
+			.join(boot_dir.strip_prefix(boot_mount_path)?)
+			.join(KERNEL_NAME);
+		let installed_kernel = mnt_path.join("boot").join(KERNEL_NAME);
+		let keep_kernel = Action::KeepKernel {
+			previous: grub::previous_kernel(&installed_kernel),
+			kernel: installed_kernel,
+		};
+		let config = Action::WriteBootConfig {
+			path: mnt_path.join("boot/grub/grub.cfg"),
+			settings: grub::Settings {
+				root_partition,
+				boot_partition,
+				kernel,
+				timeout: self.boot_timeout.unwrap_or(grub::DEFAULT_TIMEOUT),
+				kernel_args: self.kernel_args.clone(),
+				other_systems: self.other_systems.clone(),
+			},
+		};
+		Ok(vec![install, keep_kernel, config])
+	}
 
-			// TODO support nvme
-			let dev_path = format!("{}{}", self.selected_disk.display(), i + 1);
-			let mnt_path = common::util::concat_paths(Path::new("/mnt"), mnt_path);
+	/// Returns the actions installing the system in the directory at `root`: packages and
+	/// configuration.
+	fn system_actions(&self, root: &Path) -> Result<Vec<Action>, Box<dyn Error>> {
+		let lang = self.lang.as_ref().ok_or("no language selected")?;
 
-			println!("Mount `{dev_path}` at `{}`", mnt_path.display());
+		let mut actions = vec![Action::CreateDirStructure {
+			root: root.to_owned(),
+		}];
 
-			// Perform mount
-			fs::create_dir_all(&mnt_path)?;
-			let status = Command::new("mount")
-				.arg(dev_path)
-				.arg(&mnt_path)
-				.status()?;
-			if !status.success() {
-				return Err(format!("Cannot mount partition at `{}`", mnt_path.display()).into());
+		// TODO add option to use remote repo
+		let repo = Repository::local(LOCAL_REPO.into());
+		let packages = repo
+			.list_packages()
+			.map_err(|e| format!("cannot list packages of the local repository: {e}"))?;
+		actions.extend(packages.into_iter().map(|pkg| Action::InstallPackage {
+			root: root.to_owned(),
+			name: pkg.name,
+			version: pkg.version.to_string(),
+		}));
+
+		actions.push(Action::WriteLocale {
+			path: root.join("etc/locale.conf"),
+			locale: lang.get_locale().to_owned(),
+		});
+		actions.push(Action::WriteHostname {
+			path: root.join("etc/hostname"),
+			hostname: self.hostname.clone(),
+		});
+		actions.push(Action::CreateUsers {
+			root: root.to_owned(),
+			admin_user: self.admin_user.clone(),
+		});
+		Ok(actions)
+	}
+
+	/// Returns the actions setting up the swap space of the system mounted at `root`.
+	fn swap_actions(&self, root: &Path) -> Vec<Action> {
+		let mut actions = vec![];
+		if let Some(size) = self.swap_file_size {
+			actions.push(Action::CreateSwapFile {
+				path: root.join(SWAP_FILE_PATH),
+				size,
+			});
+		}
+		let swap_partitions: Vec<usize> = self
+			.partitions
+			.iter()
+			.enumerate()
+			.filter(|(_, p)| p.get_filesystem() == Filesystem::Swap)
+			.map(|(i, _)| i + 1)
+			.collect();
+		if !swap_partitions.is_empty() || self.swap_file_size.is_some() {
+			actions.push(Action::WriteFstab {
+				path: root.join("etc/fstab"),
+				swap_partitions,
+				swap_file: self
+					.swap_file_size
+					.map(|_| Path::new("/").join(SWAP_FILE_PATH)),
+			});
+		}
+		actions
+	}
+
+	/// Returns the plan of the installation.
+	///
+	/// `mnt_path` is the path at which the system's root filesystem is mounted during
+	/// installation.
+	///
+	/// Building the plan does not modify anything on the system.
+	pub fn plan(&self, mnt_path: &Path) -> Result<Vec<Action>, Box<dyn Error>> {
+		let mut plan = self.setup_actions(mnt_path);
+		plan.push(Action::WritePartitionTable {
+			disk: self.selected_disk.clone(),
+			partitions: self.partitions.clone(),
+		});
+		for (i, part) in self.partitions.iter().enumerate() {
+			let filesystem = part.get_filesystem();
+			if filesystem != Filesystem::None {
+				plan.push(Action::CreateFilesystem {
+					partition: i + 1,
+					filesystem,
+				});
 			}
 		}
-		Ok(())
+		plan.extend(self.mount_actions(mnt_path));
+		plan.extend(self.system_actions(mnt_path)?);
+		plan.extend(self.swap_actions(mnt_path));
+		plan.extend(self.bootloader_actions(mnt_path)?);
+		plan.push(Action::WriteLog {
+			path: mnt_path.join(LOG_PATH),
+		});
+		plan.extend(self.teardown_actions(mnt_path));
+		Ok(plan)
 	}
 
-	/// Installs packages on the system.
+	/// Returns the plan of the installation of the system in the existing directory at `root`.
 	///
-	/// `mnt_path` is the path to the root filesystem's mountpoint.
-	fn install_packages(&self, mnt_path: &Path) -> Result<(), Box<dyn Error>> {
-		fs::create_dir_all(mnt_path.join("usr/lib/blimp"))?;
+	/// Contrary to [`Self::plan`], the disk is neither partitioned nor mounted, and no bootloader
+	/// is installed. This allows to populate root trees for containers, chroots or network boot.
+	pub fn sysroot_plan(&self, root: &Path) -> Result<Vec<Action>, Box<dyn Error>> {
+		let mut plan = vec![Action::CreateDir {
+			path: root.to_owned(),
+		}];
+		plan.extend(self.system_actions(root)?);
+		plan.push(Action::WriteLog {
+			path: root.join(LOG_PATH),
+		});
+		Ok(plan)
+	}
 
-		let mut env = Environment::acquire(mnt_path, current_arch())?.unwrap();
-		// TODO add option to use remote repo
-		let repo = Repository::local("/local_repo".into());
+	/// Writes a GPT partition table with the given partitions on the disk.
+	fn partition_disk(
+		disk_path: &Path,
+		partitions: &[PartitionDesc],
+	) -> Result<(), Box<dyn Error>> {
+		let count = partitions.len();
+		let partitions = partitions
+			.iter()
+			.map(|desc| {
+				let part_type = PartitionType::from_str(desc.part_type.as_str())
+					.map_err(|_| format!("Invalid partition type `{}`", desc.part_type))?;
+				Ok(Partition {
+					start: desc.start,
+					size: desc.size,
+
+					part_type,
+
+					uuid: Some(Uuid::new_v4()),
+
+					bootable: desc.bootable,
+				})
+			})
+			.collect::<Result<_, String>>()?;
+		let partition_table = PartitionTable {
+			table_type: PartitionTableType::Gpt,
+			partitions,
+		};
+
+		let mut disk = Disk::read(disk_path.to_owned())?
+			.ok_or_else(|| format!("cannot read disk `{}`", disk_path.display()))?;
+		disk.partition_table = partition_table;
+		disk.write()?;
+		disk::read_partitions(disk_path)?;
+		wait_partitions(disk_path, count)?;
+
+		Ok(())
+	}
 
-		for pkg in repo.list_packages()? {
-			println!("Install `{}` (version {})...", pkg.name, pkg.version);
-			let archive_path = repo.get_archive_path(env.arch(), &pkg.name, &pkg.version);
-			env.install(&pkg, &archive_path)?;
+	/// Creates a filesystem of the given type on the device.
+	fn create_filesystem(
+		device: &Path,
+		filesystem: Filesystem,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let Some(mut cmd) = filesystem.mkfs(device) else {
+			return Ok(());
+		};
+		let output = progress.run(&mut cmd)?;
+		if !output.status.success() {
+			return Err("Filesystem creation failed!".into());
+		}
+		Ok(())
+	}
+
+	/// Mounts the device, holding the given filesystem, at the given path.
+	fn mount(
+		device: &Path,
+		filesystem: Filesystem,
+		mnt_path: &Path,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let fs_type = filesystem
+			.mount_type()
+			.ok_or_else(|| format!("Filesystem {filesystem} cannot be mounted"))?;
+		fs::create_dir_all(mnt_path)?;
+		let output = progress.run(
+			Command::new("mount")
+				.arg("-t")
+				.arg(fs_type)
+				.arg(device)
+				.arg(mnt_path),
+		)?;
+		if !output.status.success() {
+			return Err(format!("Cannot mount partition at `{}`", mnt_path.display()).into());
 		}
 		Ok(())
 	}
 
-	/// Installs the bootloader.
+	/// Generates the configuration of the bootloader, then writes it at `path`.
 	///
-	/// `mnt_path` is the path to the root filesystem's mountpoint.
-	fn install_bootloader(&self, mnt_path: &Path) -> Result<(), Box<dyn Error>> {
-		let status = Command::new("grub-install")
-			.arg("--target=i386-pc")
-			.arg(format!(
-				"--boot-directory={}",
-				mnt_path.join("boot").display()
-			))
-			.arg(&self.selected_disk)
-			.status()?;
-		if !status.success() {
-			return Err("Cannot install bootloader".into());
-		}
-
-		// Write `grub.cfg`
+	/// `disk_dev` is the path to the device file of the disk the system is installed on.
+	fn write_boot_config(
+		&self,
+		disk_dev: &Path,
+		path: &Path,
+		settings: &grub::Settings,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let root_dev = partition_path(disk_dev, settings.root_partition)?;
+		let root_uuid = blkid_value(&root_dev, "PARTUUID", progress)?;
+		let boot_dev = partition_path(disk_dev, settings.boot_partition)?;
+		let boot_fs = self
+			.partitions
+			.get(settings.boot_partition - 1)
+			.ok_or("invalid boot partition")?
+			.get_filesystem();
+		let config = grub::Config {
+			timeout: settings.timeout,
+			modules: std::iter::once("part_gpt")
+				.chain(boot_fs.grub_module())
+				.collect(),
+			boot_uuid: blkid_value(&boot_dev, "UUID", progress)?,
+			entries: settings.menu_entries(&root_uuid),
+		};
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
 		let mut file = OpenOptions::new()
 			.create(true)
 			.truncate(true)
 			.write(true)
-			.open(mnt_path.join("boot/grub/grub.cfg"))?;
-		file.write_all(include_bytes!("grub.cfg"))?;
+			.open(path)?;
+		write!(file, "{config}")?;
 		Ok(())
 	}
 
-	/// Sets localization options.
-	///
-	/// `mnt_path` is the path to the root filesystem's mountpoint.
-	fn set_locales(&self, mnt_path: &Path) -> Result<(), Box<dyn Error>> {
-		let locale = self.lang.as_ref().unwrap().get_locale();
-
-		let path = mnt_path.join("etc/locale.conf");
+	/// Writes the locale configuration file at the given path.
+	fn set_locales(path: &Path, locale: &str) -> Result<(), Box<dyn Error>> {
 		let mut file = OpenOptions::new()
 			.read(true)
 			.write(true)
@@ -257,18 +841,15 @@ impl InstallInfo {
 		Ok(())
 	}
 
-	/// Creates the hostname file.
-	///
-	/// `mnt_path` is the path to the root filesystem's mountpoint.
-	fn set_hostname(&self, mnt_path: &Path) -> Result<(), Box<dyn Error>> {
-		let path = mnt_path.join("etc/hostname");
+	/// Writes the hostname file at the given path.
+	fn set_hostname(path: &Path, hostname: &str) -> Result<(), Box<dyn Error>> {
 		let mut file = OpenOptions::new()
 			.read(true)
 			.write(true)
 			.create(true)
 			.truncate(true)
 			.open(path)?;
-		file.write_all(self.hostname.as_bytes())?;
+		file.write_all(hostname.as_bytes())?;
 
 		Ok(())
 	}
@@ -367,77 +948,790 @@ impl InstallInfo {
 		Ok(())
 	}
 
-	/// Unmounts filesystems to finalize the installation.
+	/// Appends the given logs to the file at the given path.
+	fn write_logs(path: &Path, logs: &[String]) -> Result<(), Box<dyn Error>> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.mode(0o600)
+			.open(path)?;
+		for line in logs {
+			writeln!(file, "{line}")?;
+		}
+		Ok(())
+	}
+
+	/// Creates a swap file of `size` bytes at `path`.
+	fn create_swap_file(
+		path: &Path,
+		size: u64,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let mut file = OpenOptions::new()
+			.create(true)
+			.write(true)
+			.truncate(true)
+			.mode(0o600)
+			.open(path)?;
+		// The mode is applied only if the file is created
+		file.set_permissions(Permissions::from_mode(0o600))?;
+		// Swap files must not contain holes, so the file is filled instead of being truncated to
+		// the size
+		let buf = [0u8; 65536];
+		let mut remaining = size;
+		while remaining > 0 {
+			let len = remaining.min(buf.len() as u64) as usize;
+			file.write_all(&buf[..len])?;
+			remaining -= len as u64;
+		}
+		file.sync_all()?;
+		Self::create_filesystem(path, Filesystem::Swap, progress)
+	}
+
+	/// Writes the table of filesystems at `path`, activating the given swap partitions of the disk
+	/// and swap file at boot.
 	///
-	/// `mnt_path` is the path to the root filesystem's mountpoint.
-	fn unmount_filesystems(&self, mnt_path: &Path) -> Result<(), Box<dyn Error>> {
-		let status = Command::new("umount").arg("-R").arg(mnt_path).status()?;
-		if status.success() {
+	/// Entries already present in the file are kept.
+	fn write_fstab(
+		disk_dev: &Path,
+		path: &Path,
+		swap_partitions: &[usize],
+		swap_file: Option<&Path>,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let mut content = match fs::read_to_string(path) {
+			Ok(content) => content,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+			Err(e) => return Err(e.into()),
+		};
+		let mut devices = vec![];
+		for n in swap_partitions {
+			// Partitions are referred to by UUID since device names may change across boots
+			let dev = partition_path(disk_dev, *n)?;
+			let uuid = blkid_value(&dev, "PARTUUID", progress)?;
+			devices.push(format!("PARTUUID={uuid}"));
+		}
+		devices.extend(swap_file.map(|path| path.display().to_string()));
+		for dev in devices {
+			let entry = format!("{dev}\tnone\tswap\tdefaults\t0\t0");
+			if !content.lines().any(|line| line == entry) {
+				content.push_str(&entry);
+				content.push('\n');
+			}
+		}
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(path, content)?;
+		Ok(())
+	}
+
+	/// Unmounts all filesystems under the given path.
+	fn unmount(mnt_path: &Path, progress: &mut InstallProgress) -> Result<(), Box<dyn Error>> {
+		let output = progress.run(Command::new("umount").arg("-R").arg(mnt_path))?;
+		if output.status.success() {
 			Ok(())
 		} else {
 			Err("Cannot unmount filesystems".into())
 		}
 	}
 
-	/// Performs the installation operation.
+	/// Executes the actions of the given plan from the action at index `start`, registering how to
+	/// undo them in `rollback`.
 	///
-	/// `prompt` is the prompt associated with the installation procedure.
-	pub fn perform_install(&self) -> Result<(), Box<dyn Error>> {
-		let mut progress = InstallProgress::default();
+	/// Actions before `start` are considered complete. The directories they created and the
+	/// filesystems they mounted are restored, unless the filesystems have been unmounted since.
+	fn execute_plan(
+		&self,
+		plan: &[Action],
+		start: usize,
+		opts: &ExecOptions,
+		rollback: &mut Rollback,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		// Packages are installed in an environment acquired on the first installation
+		let repo = Repository::local(LOCAL_REPO.into());
+		let mut packages = None;
+		let mut env = None;
 
-		let mnt_path = Path::new("/mnt");
-		progress.log(&format!("Create directory `{}`\n", mnt_path.display()));
-		fs::create_dir(mnt_path)?;
+		// The device on which the system is installed
+		let mut disk_dev = self.selected_disk.clone();
 
-		progress.log("\nPartition disk\n");
-		self.partition_disks()?;
+		let packages_count = plan
+			.iter()
+			.filter(|action| matches!(action, Action::InstallPackage { .. }))
+			.count();
+		let mut package_index = plan[..start.min(plan.len())]
+			.iter()
+			.filter(|action| matches!(action, Action::InstallPackage { .. }))
+			.count();
 
-		progress.log("\nCreate filesystems\n");
-		self.create_filesystems()?;
+		let unmounted = plan[..start.min(plan.len())]
+			.iter()
+			.any(|action| matches!(action, Action::Unmount { .. }));
+		let actions = plan.iter().enumerate().filter(|(i, action)| match action {
+			_ if *i >= start => true,
+			Action::CreateDir {
+				..
+			}
+			| Action::AttachImage {
+				..
+			} => true,
+			Action::Mount {
+				..
+			} => !unmounted,
+			_ => false,
+		});
+		for (i, action) in actions {
+			progress.emit(Event::PhaseStarted {
+				index: i,
+				count: plan.len(),
+				name: action.name().to_owned(),
+				description: action.to_string(),
+			});
+			match action {
+				Action::CreateDir {
+					path,
+				} => {
+					if !path.exists() {
+						fs::create_dir_all(path)?;
+						rollback.push(Undo::RemoveDir(path.clone()));
+					}
+				}
+				Action::AttachImage {
+					image,
+					size,
+				} => {
+					if let (Some(size), false) = (size, image.exists()) {
+						File::create(image)?.set_len(*size)?;
+						// The image is kept if the installation can be resumed
+						if opts.state_file.is_none() {
+							rollback.push(Undo::RemoveFile(image.clone()));
+						}
+					}
+					disk_dev = attach_loop(image, progress)?;
+					rollback.push(Undo::DetachLoop(disk_dev.clone()));
+					// When resuming or repairing, the image is already partitioned and the
+					// partitions are used right away, so wait for their device files
+					let partitioned = !plan[start..]
+						.iter()
+						.any(|action| matches!(action, Action::WritePartitionTable { .. }));
+					if partitioned {
+						wait_partitions(&disk_dev, self.partitions.len())?;
+					}
+				}
+				Action::WritePartitionTable {
+					partitions, ..
+				} => {
+					if opts.restore_table {
+						if let Some(old) = Disk::read(disk_dev.clone())? {
+							rollback.push(Undo::RestorePartitionTable(disk_dev.clone(), old));
+						}
+					}
+					Self::partition_disk(&disk_dev, partitions)?;
+				}
+				Action::CreateFilesystem {
+					partition,
+					filesystem,
+				} => Self::create_filesystem(
+					&partition_path(&disk_dev, *partition)?,
+					*filesystem,
+					progress,
+				)?,
+				Action::Mount {
+					partition,
+					path,
+				} => {
+					if !is_mounted(path)? {
+						let filesystem = self
+							.partitions
+							.get(*partition - 1)
+							.ok_or("invalid partition")?
+							.get_filesystem();
+						Self::mount(
+							&partition_path(&disk_dev, *partition)?,
+							filesystem,
+							path,
+							progress,
+						)?;
+						rollback.push(Undo::Unmount(path.clone()));
+					}
+				}
+				Action::CreateDirStructure {
+					root,
+				} => fhs::create_dirs(root, true)?,
+				Action::InstallPackage {
+					root,
+					name,
+					version,
+				} => {
+					if packages.is_none() {
+						packages = Some(repo.list_packages()?);
+					}
+					let pkg = packages
+						.iter()
+						.flatten()
+						.find(|pkg| &pkg.name == name && &pkg.version.to_string() == version)
+						.ok_or_else(|| format!("package `{name}` not found"))?;
+					package_index += 1;
+					progress.emit(Event::Package {
+						index: package_index,
+						count: packages_count,
+						name: name.clone(),
+						version: version.clone(),
+					});
 
-		progress.log("\nMount filesystems\n");
-		self.mount_filesystems()?;
+					let env = match &mut env {
+						Some(env) => env,
+						None => {
+							fs::create_dir_all(root.join("usr/lib/blimp"))?;
+							let e = Environment::acquire(root, current_arch())?
+								.ok_or("cannot acquire the packages environment")?;
+							env.insert(e)
+						}
+					};
+					let archive_path = repo.get_archive_path(env.arch(), &pkg.name, &pkg.version);
+					env.install(pkg, &archive_path)?;
+				}
+				Action::InstallBootloader {
+					bootloader,
+					boot_dir,
+					efi_dir,
+					removable,
+					..
+				} => bootloader.install(
+					&disk_dev,
+					boot_dir,
+					efi_dir.as_deref(),
+					*removable,
+					progress,
+				)?,
+				Action::KeepKernel {
+					kernel,
+					previous,
+				} => {
+					// When repairing, the previous kernel may differ from the current one
+					if !previous.exists() {
+						fs::copy(kernel, previous)?;
+					}
+				}
+				Action::WriteBootConfig {
+					path,
+					settings,
+				} => self.write_boot_config(&disk_dev, path, settings, progress)?,
+				Action::CreateSwapFile {
+					path,
+					size,
+				} => Self::create_swap_file(path, *size, progress)?,
+				Action::WriteFstab {
+					path,
+					swap_partitions,
+					swap_file,
+				} => Self::write_fstab(
+					&disk_dev,
+					path,
+					swap_partitions,
+					swap_file.as_deref(),
+					progress,
+				)?,
+				Action::WriteLocale {
+					path,
+					locale,
+				} => Self::set_locales(path, locale)?,
+				Action::WriteHostname {
+					path,
+					hostname,
+				} => Self::set_hostname(path, hostname)?,
+				Action::CreateUsers {
+					root, ..
+				} => self.create_users(root)?,
+				Action::WriteLog {
+					path,
+				} => Self::write_logs(path, progress.get_logs())?,
+				Action::Unmount {
+					path,
+				} => {
+					// Release the packages environment before unmounting
+					env = None;
+					Self::unmount(path, progress)?;
+					rollback.forget(|undo| matches!(undo, Undo::Unmount(_)));
+				}
+				Action::DetachImage {
+					..
+				} => {
+					detach_loop(&disk_dev, progress)?;
+					rollback.forget(|undo| matches!(undo, Undo::DetachLoop(_)));
+				}
+			}
 
-		progress.log("\nCreate directory structure\n");
-		fhs::create_dirs(mnt_path, true)?;
+			progress.emit(Event::PhaseFinished {
+				index: i,
+				count: plan.len(),
+			});
+			progress.emit(Event::Progress {
+				percent: ((i + 1) * 100 / plan.len()) as u8,
+			});
 
-		progress.log("\nInstall packages\n");
-		self.install_packages(mnt_path)?;
+			if let (Some(state_file), true) = (&opts.state_file, i >= start) {
+				let state = InstallState {
+					infos: self.clone(),
+					plan: plan.to_vec(),
+					completed: i + 1,
+				};
+				// Failing to record the state must not interrupt the installation
+				if let Err(e) = state.save(state_file) {
+					progress.warn(format!("Cannot record installation state: {e}"));
+				}
+			}
+		}
+		Ok(())
+	}
 
-		progress.log("\nInstall bootloader\n");
-		self.install_bootloader(mnt_path)?;
+	/// Executes the given plan, as returned by [`Self::plan`], from the action at index `start`.
+	///
+	/// If an action fails, the actions already performed are undone before returning the error:
+	/// filesystems are unmounted, and created directories and disk images are removed.
+	///
+	/// `progress` receives the logs of the installation procedure.
+	fn execute(
+		&self,
+		plan: &[Action],
+		start: usize,
+		opts: &ExecOptions,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let mut rollback = Rollback::default();
+		let result = self.execute_plan(plan, start, opts, &mut rollback, progress);
+		match result {
+			Ok(()) => {
+				if let Some(state_file) = &opts.state_file {
+					let _ = fs::remove_file(state_file);
+				}
+				progress.log("Done!\n");
+			}
+			Err(ref e) => {
+				progress.emit(Event::Failed {
+					message: e.to_string(),
+				});
+				progress.log("Rolling back...\n");
+				rollback.run(progress);
+				// The state does not match the disk anymore
+				if let (Some(state_file), true) = (&opts.state_file, opts.restore_table) {
+					let _ = fs::remove_file(state_file);
+				}
+			}
+		}
+		result
+	}
 
-		progress.log("\nSet locales\n");
-		self.set_locales(mnt_path)?;
+	/// Executes the given plan, as returned by [`Self::plan`].
+	///
+	/// Arguments:
+	/// - `opts` are the options of the execution.
+	/// - `progress` receives the logs of the installation procedure.
+	pub fn perform_install(
+		&self,
+		plan: &[Action],
+		opts: &ExecOptions,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		self.execute(plan, 0, opts, progress)
+	}
 
-		progress.log("\nSet hostname\n");
-		self.set_hostname(mnt_path)?;
+	/// Resumes an interrupted installation from the given recorded state.
+	///
+	/// Filesystems are mounted again, then the installation continues from the first action that
+	/// did not complete.
+	///
+	/// Arguments are the same as for [`Self::perform_install`].
+	pub fn resume(
+		state: &InstallState,
+		opts: &ExecOptions,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		if state.completed >= state.plan.len() {
+			let message = "the installation has already completed";
+			progress.emit(Event::Failed {
+				message: message.to_owned(),
+			});
+			return Err(message.into());
+		}
+		progress.log(&format!(
+			"Resume installation at action {} out of {}\n",
+			state.completed + 1,
+			state.plan.len()
+		));
+		state
+			.infos
+			.execute(&state.plan, state.completed, opts, progress)
+	}
 
-		progress.log("\nCreate users and groups\n");
-		self.create_users(mnt_path)?;
+	/// Reinstalls the bootloader of a system previously installed with the same information.
+	///
+	/// Arguments:
+	/// - `mnt_path` is the path at which the system's root filesystem is mounted.
+	/// - `progress` receives the logs of the repair procedure.
+	pub fn repair(
+		&self,
+		mnt_path: &Path,
+		progress: &mut InstallProgress,
+	) -> Result<(), Box<dyn Error>> {
+		let mut plan = self.setup_actions(mnt_path);
+		plan.extend(self.mount_actions(mnt_path));
+		plan.extend(self.bootloader_actions(mnt_path)?);
+		plan.push(Action::WriteLog {
+			path: mnt_path.join(LOG_PATH),
+		});
+		plan.extend(self.teardown_actions(mnt_path));
+		self.perform_install(&plan, &ExecOptions::default(), progress)
+	}
+}
 
-		progress.log("\nUnmount filesystems\n");
-		self.unmount_filesystems(mnt_path)?;
+/// Returns the path to the device file of the `n`th partition of the given disk, starting at `1`.
+///
+/// Partitions are looked up in sysfs by number, since the naming of their device files depends
+/// on the driver of the disk (`sda1`, `nvme0n1p1`, `mmcblk0p1`, `loop0p1`...).
+pub fn partition_path(disk: &Path, n: usize) -> Result<PathBuf, Box<dyn Error>> {
+	let disk = fs::canonicalize(disk)?;
+	let name = disk.file_name().ok_or("invalid disk path")?;
+	for entry in fs::read_dir(Path::new("/sys/class/block").join(name))? {
+		let entry = entry?;
+		let Ok(number) = fs::read_to_string(entry.path().join("partition")) else {
+			continue;
+		};
+		if number.trim().parse() == Ok(n) {
+			return Ok(Path::new("/dev").join(entry.file_name()));
+		}
+	}
+	Err(format!("cannot find partition {n} of disk `{}`", disk.display()).into())
+}
+
+/// Waits until the kernel has created the device files of the first `count` partitions of the
+/// given disk.
+///
+/// Device files are created asynchronously after the partition table is read.
+fn wait_partitions(disk: &Path, count: usize) -> Result<(), Box<dyn Error>> {
+	let start = Instant::now();
+	loop {
+		let ready = (1..=count).all(|n| partition_path(disk, n).is_ok_and(|path| path.exists()));
+		if ready {
+			return Ok(());
+		}
+		if start.elapsed() >= PARTITIONS_TIMEOUT {
+			return Err(format!(
+				"timeout while waiting for the partitions of `{}`",
+				disk.display()
+			)
+			.into());
+		}
+		thread::sleep(Duration::from_millis(100));
+	}
+}
 
-		progress.log("\nDone!\n");
+/// Returns the value of the given `blkid` tag of the device, such as the UUID of the filesystem
+/// on it.
+fn blkid_value(
+	dev: &Path,
+	tag: &str,
+	progress: &mut InstallProgress,
+) -> Result<String, Box<dyn Error>> {
+	let output = progress.run(
+		Command::new("blkid")
+			.arg("-s")
+			.arg(tag)
+			.arg("-o")
+			.arg("value")
+			.arg(dev),
+	)?;
+	let value = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	if !output.status.success() || value.is_empty() {
+		return Err(format!("Cannot get the {tag} of `{}`", dev.display()).into());
+	}
+	Ok(value)
+}
 
+/// Attaches the given disk image file to a free loop device, scanning its partitions.
+///
+/// The function returns the path to the loop device.
+fn attach_loop(image: &Path, progress: &mut InstallProgress) -> Result<PathBuf, Box<dyn Error>> {
+	let output = progress.run(
+		Command::new("losetup")
+			.args(["--find", "--show", "--partscan"])
+			.arg(image),
+	)?;
+	if !output.status.success() {
+		return Err(format!("Cannot attach disk image `{}`", image.display()).into());
+	}
+	let dev = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	Ok(dev.into())
+}
+
+/// Detaches the given loop device.
+fn detach_loop(dev: &Path, progress: &mut InstallProgress) -> Result<(), Box<dyn Error>> {
+	let output = progress.run(Command::new("losetup").arg("--detach").arg(dev))?;
+	if !output.status.success() {
+		return Err(format!("Cannot detach loop device `{}`", dev.display()).into());
+	}
+	Ok(())
+}
+
+/// Checks the given hostname is made of labels of letters, digits and dashes, separated by dots.
+pub fn check_hostname(hostname: &str) -> Result<(), String> {
+	let valid_label = |label: &str| {
+		(1..=63).contains(&label.len())
+			&& !label.starts_with('-')
+			&& !label.ends_with('-')
+			&& label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+	};
+	if hostname.len() <= 253 && hostname.split('.').all(valid_label) {
 		Ok(())
+	} else {
+		Err(format!("invalid hostname `{hostname}`"))
 	}
 }
 
-/// The current progress of the installation.
+/// Tells whether a filesystem is mounted at the given path.
+fn is_mounted(path: &Path) -> io::Result<bool> {
+	let mounts = fs::read_to_string("/proc/self/mounts")?;
+	Ok(mounts
+		.lines()
+		.filter_map(|line| line.split_whitespace().nth(1))
+		.any(|mnt_path| Path::new(mnt_path) == path))
+}
+
+/// Options of the execution of an installation plan.
 #[derive(Default)]
-pub struct InstallProgress {
-	/// Logs.
-	logs: Vec<String>,
+pub struct ExecOptions {
+	/// If set, the partition table the disk had before installation is written back if the
+	/// installation fails.
+	pub restore_table: bool,
+	/// The file in which the state of the installation is recorded, allowing to resume it.
+	///
+	/// If None, the state is not recorded.
+	pub state_file: Option<PathBuf>,
 }
 
-impl InstallProgress {
-	/// Inserts the given logs.
-	pub fn log(&mut self, s: &str) {
-		print!("{s}");
-		self.logs.extend(&mut s.split('\n').map(str::to_owned));
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::hw::Firmware;
+
+	/// Returns hardware with the given amount of memory in bytes.
+	fn hardware(ram: u64) -> Hardware {
+		Hardware {
+			firmware: Firmware::Bios,
+			arch: "x86_64".to_owned(),
+			cpu_model: String::new(),
+			ram,
+			disks: None,
+			input_devices: None,
+		}
+	}
+
+	/// One gibibyte, in sectors.
+	const GIB_SECTORS: u64 = 1024 * 1024 * 1024 / SECTOR_SIZE;
+
+	#[test]
+	fn auto_layout() {
+		let hw = hardware(GIB_SECTORS * SECTOR_SIZE);
+		let disk_size = 20 * GIB_SECTORS;
+		let parts =
+			PartitionDesc::auto_layout(disk_size, &hw, Bootloader::Hybrid, GIB_SECTORS).unwrap();
+		let types: Vec<&str> = parts.iter().map(|p| p.part_type.as_str()).collect();
+		assert_eq!(
+			types,
+			[
+				BIOS_BOOT_TYPE,
+				ESP_TYPE,
+				SWAP_TYPE,
+				"4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709"
+			]
+		);
+		assert_eq!(parts[2].size, GIB_SECTORS);
+		// Partitions follow each other up to the end of the disk
+		assert_eq!(parts[0].start, 2048);
+		for pair in parts.windows(2) {
+			assert_eq!(pair[0].start + pair[0].size, pair[1].start);
+		}
+		let root = parts.last().unwrap();
+		assert_eq!(root.start + root.size, disk_size);
+		assert_eq!(root.mount_path.as_deref(), Some(Path::new("/")));
+	}
+
+	#[test]
+	fn auto_layout_swap_limit() {
+		let hw = hardware(0);
+		// The swap partition takes at most half of the space left, rounded down to a mebibyte
+		let disk_size = 2048 + 10000;
+		let parts =
+			PartitionDesc::auto_layout(disk_size, &hw, Bootloader::None, GIB_SECTORS).unwrap();
+		assert_eq!(parts.len(), 2);
+		assert_eq!(parts[0].size, 4096);
+		assert_eq!(parts[1].start, 2048 + 4096);
+		assert_eq!(parts[1].size, 10000 - 4096);
+		// Without swap, the root partition takes all the space
+		let parts = PartitionDesc::auto_layout(disk_size, &hw, Bootloader::None, 0).unwrap();
+		assert_eq!(parts.len(), 1);
+		assert_eq!(parts[0].size, 10000);
+	}
+
+	#[test]
+	fn auto_layout_small_disk() {
+		let hw = hardware(0);
+		for disk_size in [0, 2048, 4096] {
+			assert!(
+				PartitionDesc::auto_layout(disk_size, &hw, Bootloader::GrubBios, GIB_SECTORS)
+					.is_err()
+			);
+		}
+		let parts =
+			PartitionDesc::auto_layout(4097, &hw, Bootloader::GrubBios, GIB_SECTORS).unwrap();
+		// There is no room for swap
+		assert_eq!(parts.len(), 2);
+		assert_eq!(parts[1].size, 1);
+	}
+
+	/// Returns valid information, installing on a disk image.
+	fn install_info() -> InstallInfo {
+		InstallInfo {
+			lang: Some(
+				serde_json::from_str(
+					r#"{"name": "en", "display_name": "English", "locale": "en_US.UTF-8"}"#,
+				)
+				.unwrap(),
+			),
+			hostname: "maestro".to_owned(),
+			admin_user: "admin".to_owned(),
+			admin_pass: "hash".to_owned(),
+			selected_disk: "maestro.img".into(),
+			image_size: Some(GIB_SECTORS * SECTOR_SIZE),
+			partitions: vec![
+				PartitionDesc {
+					start: 2048,
+					size: 2048,
+					part_type: SWAP_TYPE.to_owned(),
+					bootable: false,
+					mount_path: None,
+					filesystem: None,
+				},
+				PartitionDesc {
+					start: 4096,
+					size: GIB_SECTORS - 4096,
+					part_type: "44479540-F297-41B2-9AF7-D131D5F0458A".to_owned(),
+					bootable: false,
+					mount_path: Some("/".into()),
+					filesystem: None,
+				},
+			],
+			bootloader: Some(Bootloader::None),
+			..Default::default()
+		}
+	}
+
+	/// Returns the error returned by the validation of `infos`.
+	fn validate_err(infos: &InstallInfo) -> String {
+		match infos.validate() {
+			Ok(()) => panic!("invalid information accepted"),
+			Err(e) => e.to_string(),
+		}
+	}
+
+	#[test]
+	fn validate() {
+		install_info().validate().unwrap();
 	}
 
-	// TODO get_logs
+	#[test]
+	fn validate_overlap() {
+		let mut infos = install_info();
+		infos.partitions[1].start = 3072;
+		assert_eq!(
+			validate_err(&infos),
+			"partition 2: overlaps another partition"
+		);
+		// Partitions given in any order are checked
+		infos.partitions.reverse();
+		assert_eq!(
+			validate_err(&infos),
+			"partition 2: overlaps another partition"
+		);
+	}
+
+	#[test]
+	fn validate_bounds() {
+		let mut infos = install_info();
+		infos.partitions[1].size += 1;
+		assert_eq!(
+			validate_err(&infos),
+			"partition 2: out of the disk's bounds"
+		);
+		infos.partitions[1].size = 0;
+		assert_eq!(
+			validate_err(&infos),
+			"partition 2: out of the disk's bounds"
+		);
+	}
+
+	#[test]
+	fn validate_missing_root() {
+		let mut infos = install_info();
+		infos.partitions[1].mount_path = Some("/home".into());
+		assert_eq!(validate_err(&infos), "no partition is mounted at `/`");
+		infos.partitions.pop();
+		assert_eq!(validate_err(&infos), "no partition is mounted at `/`");
+	}
+
+	#[test]
+	fn validate_hostname() {
+		let mut infos = install_info();
+		infos.hostname = String::new();
+		assert_eq!(validate_err(&infos), "no hostname specified");
+		for hostname in [
+			"my host", "-maestro", "maestro-", "a..b", "maestro.", "mae_stro", "é",
+		] {
+			infos.hostname = hostname.to_owned();
+			assert_eq!(
+				validate_err(&infos),
+				format!("invalid hostname `{hostname}`")
+			);
+		}
+		infos.hostname = "a".repeat(64);
+		assert!(infos.validate().is_err());
+		for hostname in ["maestro-1", "host.example.org", &"a".repeat(63)] {
+			infos.hostname = hostname.to_owned();
+			infos.validate().unwrap();
+		}
+	}
+
+	#[test]
+	fn default_swap_size() {
+		// Twice the amount of memory
+		let hw = hardware(GIB_SECTORS * SECTOR_SIZE);
+		assert_eq!(
+			PartitionDesc::default_swap_size(100 * GIB_SECTORS, &hw),
+			2 * GIB_SECTORS
+		);
+		// A quarter of the disk at most
+		assert_eq!(
+			PartitionDesc::default_swap_size(4 * GIB_SECTORS, &hw),
+			GIB_SECTORS
+		);
+		assert_eq!(PartitionDesc::default_swap_size(0, &hw), 0);
+		// Large amounts of memory are not doubled
+		assert_eq!(
+			PartitionDesc::default_swap_size(
+				100 * GIB_SECTORS,
+				&hardware(3 * GIB_SECTORS * SECTOR_SIZE)
+			),
+			3 * GIB_SECTORS
+		);
+		// Memory that could not be read
+		assert_eq!(
+			PartitionDesc::default_swap_size(100 * GIB_SECTORS, &hardware(0)),
+			2 * GIB_SECTORS
+		);
+	}
 }
@@ -0,0 +1,311 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! An installation is first turned into a plan, which is a list of actions.
+//!
+//! The plan can be reviewed before being executed, without touching any disk.
+
+use super::{bootloader::Bootloader, filesystem::Filesystem, grub, PartitionDesc};
+use common::maestro_utils::util::ByteSize;
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf};
+
+/// An action performed during installation.
+///
+/// Partitions are referred to by their number on the disk, starting at `1`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+	/// Creates the directory on which the system is mounted.
+	CreateDir { path: PathBuf },
+	/// Attaches a disk image file to a loop device, so that the system can be installed on it.
+	///
+	/// If `size` is set and the file does not exist, it is created with this size in bytes.
+	AttachImage { image: PathBuf, size: Option<u64> },
+	/// Wipes the disk and writes a GPT partition table with the given partitions.
+	WritePartitionTable {
+		disk: PathBuf,
+		partitions: Vec<PartitionDesc>,
+	},
+	/// Creates a filesystem on a partition.
+	CreateFilesystem {
+		partition: usize,
+		filesystem: Filesystem,
+	},
+	/// Mounts a partition.
+	Mount { partition: usize, path: PathBuf },
+	/// Creates the directory structure of the system.
+	CreateDirStructure { root: PathBuf },
+	/// Installs a package from the local repository on the system mounted at `root`.
+	InstallPackage {
+		root: PathBuf,
+		name: String,
+		version: String,
+	},
+	/// Installs the bootloader on the disk.
+	///
+	/// If the bootloader boots with UEFI, it is installed in the EFI System partition mounted at
+	/// `efi_dir`, at the removable media path if `removable` is set.
+	InstallBootloader {
+		bootloader: Bootloader,
+		disk: PathBuf,
+		boot_dir: PathBuf,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		efi_dir: Option<PathBuf>,
+		#[serde(default)]
+		removable: bool,
+	},
+	/// Copies the installed kernel at `kernel` to `previous`, unless a file already exists there.
+	///
+	/// The copy is booted by the previous kernel entry of the boot menu.
+	KeepKernel { kernel: PathBuf, previous: PathBuf },
+	/// Writes the configuration file of the bootloader at `path`.
+	WriteBootConfig {
+		path: PathBuf,
+		settings: grub::Settings,
+	},
+	/// Creates a swap file of `size` bytes at `path`.
+	CreateSwapFile { path: PathBuf, size: u64 },
+	/// Writes the table of filesystems at `path`, activating the given swap partitions and swap
+	/// file at boot.
+	///
+	/// `swap_file` is the path to the swap file in the installed system.
+	WriteFstab {
+		path: PathBuf,
+		swap_partitions: Vec<usize>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		swap_file: Option<PathBuf>,
+	},
+	/// Writes the locale configuration file.
+	WriteLocale { path: PathBuf, locale: String },
+	/// Writes the hostname file.
+	WriteHostname { path: PathBuf, hostname: String },
+	/// Creates the root and admin users, along with their groups.
+	CreateUsers { root: PathBuf, admin_user: String },
+	/// Writes the logs of the installation so far to the given file, in the installed system.
+	WriteLog { path: PathBuf },
+	/// Unmounts all filesystems under the given path.
+	Unmount { path: PathBuf },
+	/// Detaches the disk image file from its loop device.
+	DetachImage { image: PathBuf },
+}
+
+impl Action {
+	/// Returns the name of the kind of the action, as written when serialized.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::CreateDir {
+				..
+			} => "create_dir",
+			Self::AttachImage {
+				..
+			} => "attach_image",
+			Self::WritePartitionTable {
+				..
+			} => "write_partition_table",
+			Self::CreateFilesystem {
+				..
+			} => "create_filesystem",
+			Self::Mount {
+				..
+			} => "mount",
+			Self::CreateDirStructure {
+				..
+			} => "create_dir_structure",
+			Self::InstallPackage {
+				..
+			} => "install_package",
+			Self::InstallBootloader {
+				..
+			} => "install_bootloader",
+			Self::KeepKernel {
+				..
+			} => "keep_kernel",
+			Self::WriteBootConfig {
+				..
+			} => "write_boot_config",
+			Self::CreateSwapFile {
+				..
+			} => "create_swap_file",
+			Self::WriteFstab {
+				..
+			} => "write_fstab",
+			Self::WriteLocale {
+				..
+			} => "write_locale",
+			Self::WriteHostname {
+				..
+			} => "write_hostname",
+			Self::CreateUsers {
+				..
+			} => "create_users",
+			Self::WriteLog {
+				..
+			} => "write_log",
+			Self::Unmount {
+				..
+			} => "unmount",
+			Self::DetachImage {
+				..
+			} => "detach_image",
+		}
+	}
+}
+
+impl fmt::Display for Action {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::CreateDir {
+				path,
+			} => write!(fmt, "Create directory `{}`", path.display()),
+			Self::AttachImage {
+				image,
+				size: Some(size),
+			} => write!(
+				fmt,
+				"Attach disk image `{}` (created with {} if missing)",
+				image.display(),
+				ByteSize(*size)
+			),
+			Self::AttachImage {
+				image,
+				size: None,
+			} => write!(fmt, "Attach disk image `{}`", image.display()),
+			Self::WritePartitionTable {
+				disk,
+				partitions,
+			} => write!(
+				fmt,
+				"Write GPT on `{}` with {} partitions",
+				disk.display(),
+				partitions.len()
+			),
+			Self::CreateFilesystem {
+				partition,
+				filesystem,
+			} => write!(
+				fmt,
+				"Create {filesystem} filesystem on partition {partition}"
+			),
+			Self::Mount {
+				partition,
+				path,
+			} => write!(fmt, "Mount partition {partition} at `{}`", path.display()),
+			Self::CreateDirStructure {
+				root,
+			} => write!(fmt, "Create directory structure in `{}`", root.display()),
+			Self::InstallPackage {
+				name,
+				version,
+				..
+			} => write!(fmt, "Install package `{name}` (version {version})"),
+			Self::InstallBootloader {
+				bootloader,
+				disk,
+				boot_dir,
+				efi_dir,
+				removable,
+			} => {
+				write!(
+					fmt,
+					"Install bootloader {bootloader} on `{}` with boot directory `{}`",
+					disk.display(),
+					boot_dir.display()
+				)?;
+				if let Some(efi_dir) = efi_dir {
+					write!(fmt, " and EFI directory `{}`", efi_dir.display())?;
+					if *removable {
+						write!(fmt, " at the removable media path")?;
+					}
+				}
+				Ok(())
+			}
+			Self::KeepKernel {
+				kernel,
+				previous,
+			} => write!(
+				fmt,
+				"Keep a copy of kernel `{}` at `{}`",
+				kernel.display(),
+				previous.display()
+			),
+			Self::WriteBootConfig {
+				path,
+				settings,
+			} => write!(
+				fmt,
+				"Write bootloader configuration to `{}` (kernel `{}` on partition {}, root \
+				 partition {})",
+				path.display(),
+				settings.kernel.display(),
+				settings.boot_partition,
+				settings.root_partition
+			),
+			Self::CreateSwapFile {
+				path,
+				size,
+			} => write!(
+				fmt,
+				"Create swap file `{}` of {}",
+				path.display(),
+				ByteSize(*size)
+			),
+			Self::WriteFstab {
+				path,
+				swap_partitions,
+				swap_file,
+			} => {
+				write!(fmt, "Write filesystems table to `{}`", path.display())?;
+				if !swap_partitions.is_empty() {
+					let partitions: Vec<String> =
+						swap_partitions.iter().map(ToString::to_string).collect();
+					write!(fmt, " with swap on partitions {}", partitions.join(", "))?;
+				}
+				if let Some(swap_file) = swap_file {
+					write!(fmt, " with swap file `{}`", swap_file.display())?;
+				}
+				Ok(())
+			}
+			Self::WriteLocale {
+				path,
+				locale,
+			} => write!(fmt, "Write locale `{locale}` to `{}`", path.display()),
+			Self::WriteHostname {
+				path,
+				hostname,
+			} => write!(fmt, "Write hostname `{hostname}` to `{}`", path.display()),
+			Self::CreateUsers {
+				root,
+				admin_user,
+			} => write!(
+				fmt,
+				"Create users `root` and `{admin_user}` in `{}`",
+				root.display()
+			),
+			Self::WriteLog {
+				path,
+			} => write!(fmt, "Write installation logs to `{}`", path.display()),
+			Self::Unmount {
+				path,
+			} => write!(fmt, "Unmount filesystems under `{}`", path.display()),
+			Self::DetachImage {
+				image,
+			} => write!(fmt, "Detach disk image `{}`", image.display()),
+		}
+	}
+}
@@ -0,0 +1,194 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The installation reports its progress as a stream of events, to which frontends subscribe.
+
+use serde::Serialize;
+use std::{
+	error::Error,
+	process::{Command, Output},
+};
+
+/// An event reported during installation.
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+	/// A phase of the installation started.
+	///
+	/// `index` is the index of the phase, out of `count` phases.
+	PhaseStarted {
+		index: usize,
+		count: usize,
+		name: String,
+		description: String,
+	},
+	/// A phase of the installation finished successfully.
+	PhaseFinished { index: usize, count: usize },
+	/// The installation failed, the last started phase being the one which failed.
+	Failed { message: String },
+	/// The overall progress of the installation, in percent.
+	Progress { percent: u8 },
+	/// A package is being installed. `index` starts at `1`.
+	Package {
+		index: usize,
+		count: usize,
+		name: String,
+		version: String,
+	},
+	/// An external command started.
+	CommandStarted { command: String },
+	/// An external command exited. `status` is None if it has been killed by a signal.
+	CommandExited {
+		command: String,
+		status: Option<i32>,
+	},
+	/// An issue which does not prevent the installation from continuing.
+	Warning { message: String },
+	/// A message without a more specific meaning, such as the output of commands.
+	Log { message: String },
+}
+
+impl Event {
+	/// Returns the text to show to the user on a text frontend, if any.
+	///
+	/// Events which don't carry information the user needs to read, such as the completion of a
+	/// phase, have no text.
+	pub fn text(&self) -> Option<String> {
+		match self {
+			Self::PhaseStarted {
+				index,
+				count,
+				description,
+				..
+			} => Some(format!("[{}/{count}] {description}\n", index + 1)),
+			Self::Failed {
+				message,
+			} => Some(format!("Action failed: {message}\n")),
+			Self::CommandStarted {
+				command,
+			} => Some(format!("$ {command}\n")),
+			Self::CommandExited {
+				command,
+				status,
+			} => match status {
+				Some(0) => None,
+				Some(status) => Some(format!("`{command}` exited with status {status}\n")),
+				None => Some(format!("`{command}` has been killed\n")),
+			},
+			Self::Warning {
+				message,
+			} => Some(format!("Warning: {message}\n")),
+			Self::Log {
+				message,
+			} => Some(message.clone()),
+			Self::PhaseFinished {
+				..
+			}
+			| Self::Progress {
+				..
+			}
+			| Self::Package {
+				..
+			} => None,
+		}
+	}
+}
+
+/// A function called with each reported event.
+pub type Subscriber<'l> = Box<dyn FnMut(&Event) + 'l>;
+
+/// The current progress of the installation.
+#[derive(Default)]
+pub struct InstallProgress<'l> {
+	/// Logs.
+	logs: Vec<String>,
+
+	/// Functions called with each reported event.
+	subscribers: Vec<Subscriber<'l>>,
+}
+
+impl<'l> InstallProgress<'l> {
+	/// Registers a function to be called with each reported event.
+	pub fn subscribe<S: FnMut(&Event) + 'l>(&mut self, subscriber: S) {
+		self.subscribers.push(Box::new(subscriber));
+	}
+
+	/// Reports the given event to subscribers.
+	pub fn emit(&mut self, event: Event) {
+		for subscriber in &mut self.subscribers {
+			subscriber(&event);
+		}
+		if let Some(text) = event.text() {
+			self.logs.extend(text.lines().map(str::to_owned));
+		}
+	}
+
+	/// Inserts the given logs.
+	pub fn log(&mut self, s: &str) {
+		self.emit(Event::Log {
+			message: s.to_owned(),
+		});
+	}
+
+	/// Reports the given warning.
+	pub fn warn(&mut self, message: String) {
+		self.emit(Event::Warning {
+			message,
+		});
+	}
+
+	/// Runs the given command, reporting when it starts and exits.
+	///
+	/// The output of the command is captured and reported as logs, so that it does not garble the
+	/// user interface. The function returns the output, regardless of the exit status.
+	pub fn run(&mut self, cmd: &mut Command) -> Result<Output, Box<dyn Error>> {
+		let command = std::iter::once(cmd.get_program())
+			.chain(cmd.get_args())
+			.map(|arg| arg.to_string_lossy())
+			.collect::<Vec<_>>()
+			.join(" ");
+		self.emit(Event::CommandStarted {
+			command: command.clone(),
+		});
+
+		let output = cmd
+			.output()
+			.map_err(|e| format!("Cannot run `{command}`: {e}"))?;
+		for out in [&output.stdout, &output.stderr] {
+			let mut out = String::from_utf8_lossy(out).into_owned();
+			if out.trim().is_empty() {
+				continue;
+			}
+			if !out.ends_with('\n') {
+				out.push('\n');
+			}
+			self.log(&out);
+		}
+
+		self.emit(Event::CommandExited {
+			command,
+			status: output.status.code(),
+		});
+		Ok(output)
+	}
+
+	/// Returns the lines of text of the events reported so far.
+	pub fn get_logs(&self) -> &[String] {
+		&self.logs
+	}
+}
@@ -0,0 +1,106 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! When an installation fails, the actions already performed are undone so that the system is
+//! left as it was, allowing to retry without rebooting.
+
+use super::progress::InstallProgress;
+use common::maestro_utils::disk::{self, Disk};
+use std::{error::Error, fmt, fs, path::PathBuf, process::Command};
+
+/// An action undoing a performed installation action.
+pub enum Undo {
+	/// Removes the directory at the given path.
+	RemoveDir(PathBuf),
+	/// Removes the file at the given path.
+	RemoveFile(PathBuf),
+	/// Unmounts the filesystem mounted at the given path.
+	Unmount(PathBuf),
+	/// Detaches the loop device at the given path.
+	DetachLoop(PathBuf),
+	/// Writes back the partition table the disk at the given path had before installation.
+	RestorePartitionTable(PathBuf, Disk),
+}
+
+impl Undo {
+	/// Performs the action.
+	fn perform(self, progress: &mut InstallProgress) -> Result<(), Box<dyn Error>> {
+		match self {
+			Self::RemoveDir(path) => fs::remove_dir(path)?,
+			Self::RemoveFile(path) => fs::remove_file(path)?,
+			Self::Unmount(path) => {
+				let output = progress.run(Command::new("umount").arg(&path))?;
+				if !output.status.success() {
+					return Err(format!("Cannot unmount `{}`", path.display()).into());
+				}
+			}
+			Self::DetachLoop(path) => super::detach_loop(&path, progress)?,
+			Self::RestorePartitionTable(path, disk) => {
+				disk.write()?;
+				disk::read_partitions(&path)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for Undo {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::RemoveDir(path) => write!(fmt, "Remove directory `{}`", path.display()),
+			Self::RemoveFile(path) => write!(fmt, "Remove file `{}`", path.display()),
+			Self::Unmount(path) => write!(fmt, "Unmount `{}`", path.display()),
+			Self::DetachLoop(path) => write!(fmt, "Detach loop device `{}`", path.display()),
+			Self::RestorePartitionTable(path, _) => {
+				write!(fmt, "Restore the partition table of `{}`", path.display())
+			}
+		}
+	}
+}
+
+/// The stack of actions to undo if the installation fails.
+#[derive(Default)]
+pub struct Rollback {
+	/// The actions, in the order they have been registered.
+	undo: Vec<Undo>,
+}
+
+impl Rollback {
+	/// Registers an action to be performed on rollback.
+	pub fn push(&mut self, undo: Undo) {
+		self.undo.push(undo);
+	}
+
+	/// Forgets the registered actions for which `f` returns `true`, after the installation has
+	/// undone them itself.
+	pub fn forget<F: Fn(&Undo) -> bool>(&mut self, f: F) {
+		self.undo.retain(|undo| !f(undo));
+	}
+
+	/// Performs registered actions, in the reverse order of registration.
+	///
+	/// A failing action does not prevent the next ones from being performed.
+	pub fn run(self, progress: &mut InstallProgress) {
+		for undo in self.undo.into_iter().rev() {
+			progress.log(&format!("{undo}\n"));
+			if let Err(e) = undo.perform(progress) {
+				progress.warn(format!("Rollback action failed: {e}"));
+			}
+		}
+	}
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The state of an installation is recorded in a file as actions complete, allowing to resume an
+//! interrupted installation from the first action that did not complete.
+
+use super::{plan::Action, InstallInfo};
+use serde::{Deserialize, Serialize};
+use std::{
+	error::Error,
+	fs,
+	fs::{File, OpenOptions, Permissions},
+	io::BufReader,
+	os::unix::fs::{OpenOptionsExt, PermissionsExt},
+	path::Path,
+};
+
+/// The recorded state of an installation.
+#[derive(Deserialize, Serialize)]
+pub struct InstallState {
+	/// The installation information.
+	pub infos: InstallInfo,
+	/// The plan of the installation.
+	pub plan: Vec<Action>,
+	/// The number of actions of the plan that completed.
+	pub completed: usize,
+}
+
+impl InstallState {
+	/// Loads the state from the file at the given path.
+	pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+		let file = File::open(path)?;
+		let reader = BufReader::new(file);
+		Ok(serde_json::from_reader(reader)?)
+	}
+
+	/// Saves the state to the file at the given path.
+	///
+	/// The file contains the admin password hash, so it is readable only by its owner.
+	pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		// Write to a temporary file first so that an interruption cannot corrupt the state
+		let tmp_path = path.with_extension("tmp");
+		let file = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.mode(0o600)
+			.open(&tmp_path)?;
+		// The mode is applied only if the file is created
+		file.set_permissions(Permissions::from_mode(0o600))?;
+		serde_json::to_writer(file, self)?;
+		fs::rename(tmp_path, path)?;
+		Ok(())
+	}
+}
@@ -18,39 +18,405 @@
 
 //! Installation utility for the Maestro operating system.
 
+mod cli;
+mod hw;
 mod install;
 mod lang;
 mod prompt;
 mod util;
 
+use cli::{Args, Command, Frontend, USAGE};
+use common::maestro_utils::{
+	disk::Disk,
+	util::{get_timestamp, ByteSize},
+};
+use install::{
+	grub,
+	plan::Action,
+	progress::{Event, InstallProgress},
+	state::InstallState,
+	ExecOptions, InstallInfo,
+};
+use lang::Language;
 use prompt::{
-	term::{TermPrompt, CODE_RED, CODE_RESET},
+	dialog::DialogPrompt,
+	gui::{
+		fb::{DevFramebuffer, MemFramebuffer},
+		Gui,
+	},
+	term::{prompt_yes_no, TermDialog, CODE_GREEN, CODE_RED, CODE_RESET},
+	tui::Tui,
 	InstallPrompt, InstallStep,
 };
-use std::{env, process::exit};
+use serde::Serialize;
+use std::{
+	env,
+	fs::{File, OpenOptions},
+	io::Write,
+	path::Path,
+	process::exit,
+	sync::atomic::{AtomicBool, Ordering},
+};
 
-fn main() {
-	// Get prompt type
-	let prompt_type = env::args().nth(1);
-	let prompt_type = prompt_type.as_deref().unwrap_or("term");
-	// Create prompt
-	let mut prompt = match prompt_type {
-		"term" => TermPrompt::new(),
-		// TODO Add support for GUI
-		_ => {
-			eprintln!("Invalid prompt type: {prompt_type}");
-			exit(1);
+/// Tells whether the output is made of JSON objects for programs instead of text for humans.
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// A line of the JSON output.
+#[derive(Serialize)]
+struct JsonRecord<'e> {
+	/// The time at which the event occurred, in milliseconds since the Unix epoch.
+	timestamp: u64,
+	/// The event.
+	#[serde(flatten)]
+	event: &'e Event,
+}
+
+/// Prints the given event as a line of JSON on the standard output.
+fn print_json(event: &Event) {
+	let record = JsonRecord {
+		timestamp: get_timestamp().as_millis() as u64,
+		event,
+	};
+	if let Ok(json) = serde_json::to_string(&record) {
+		println!("{json}");
+	}
+}
+
+/// Prints the given error, then exits the program.
+fn fatal(msg: &str) -> ! {
+	if JSON_OUTPUT.load(Ordering::Relaxed) {
+		print_json(&Event::Failed {
+			message: msg.to_owned(),
+		});
+	} else {
+		eprintln!("{CODE_RED}{msg}{CODE_RESET}");
+	}
+	exit(1);
+}
+
+/// Prints the given message for humans, unless the output is JSON.
+fn print_finished(msg: &str) {
+	if !JSON_OUTPUT.load(Ordering::Relaxed) {
+		println!("{CODE_GREEN}{msg}{CODE_RESET}");
+	}
+}
+
+/// Opens the file to which installation logs are appended, if any.
+fn open_log_file(args: &Args) -> Option<File> {
+	let path = args.log_file.as_ref()?;
+	let file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+		.unwrap_or_else(|e| fatal(&format!("Cannot open log file `{}`: {e}", path.display())));
+	Some(file)
+}
+
+/// Creates the progress of an installation.
+///
+/// Events are reported to the user with `report`, and their text is appended to `log_file` if
+/// any.
+fn new_progress<'r, R: FnMut(&Event) + 'r>(
+	mut log_file: Option<File>,
+	report: R,
+) -> InstallProgress<'r> {
+	let mut progress = InstallProgress::default();
+	progress.subscribe(report);
+	progress.subscribe(move |event| {
+		if let (Some(file), Some(text)) = (&mut log_file, event.text()) {
+			// Failing to write logs must not interrupt the installation
+			let _ = file.write_all(text.as_bytes());
+		}
+	});
+	progress
+}
+
+/// Prints the given event on the standard output, as JSON if requested.
+fn print_event(event: &Event) {
+	if JSON_OUTPUT.load(Ordering::Relaxed) {
+		print_json(event);
+	} else if let Some(text) = event.text() {
+		print!("{text}");
+	}
+}
+
+/// Returns the options of the execution of an installation.
+fn exec_options(args: &Args) -> ExecOptions {
+	ExecOptions {
+		restore_table: args.restore_partitions,
+		state_file: args.state_file.clone(),
+	}
+}
+
+/// Exits the program after an installation failed.
+///
+/// The error is not printed since the progress of the installation already reports it, as an
+/// [`Event::Failed`].
+fn install_failed(args: &Args) -> ! {
+	if !args.json {
+		eprintln!("{CODE_RED}Installation failed{CODE_RESET}");
+		if let (Some(state_file), false) = (&args.state_file, args.restore_partitions) {
+			eprintln!(
+				"The installation can be resumed with `install resume --state-file {}`",
+				state_file.display()
+			);
 		}
+	}
+	exit(1);
+}
+
+/// Loads and validates the answer file at the given path.
+///
+/// When installing in a directory, information about the disk is not required.
+///
+/// On failure, the function exits the program.
+fn load_answer_file(args: &Args, path: &Path) -> InstallInfo {
+	let mut infos = InstallInfo::load(path).unwrap_or_else(|e| {
+		fatal(&format!(
+			"Cannot read answer file `{}`: {e}",
+			path.display()
+		))
+	});
+	// Options given on the command line take precedence
+	if args.bootloader.is_some() {
+		infos.bootloader = args.bootloader;
+	}
+	infos.efi_removable |= args.efi_removable;
+	if args.carry_kernel_args {
+		infos.kernel_args.extend(grub::live_kernel_args());
+	}
+	let result = match args.sysroot {
+		Some(_) => infos.validate_system(),
+		None => infos.validate(),
+	};
+	if let Err(e) = result {
+		fatal(&format!("Invalid answer file: {e}"));
+	}
+	infos
+}
+
+/// Returns the plan of the installation, either on a disk or in the directory given with
+/// `--sysroot`.
+///
+/// On failure, the function exits the program.
+fn install_plan(args: &Args, infos: &InstallInfo) -> Vec<Action> {
+	let plan = match &args.sysroot {
+		Some(root) => infos.sysroot_plan(root),
+		None => infos.plan(&args.target_root),
 	};
+	plan.unwrap_or_else(|e| fatal(&format!("Cannot plan installation: {e}")))
+}
 
+/// Prints the given plan of an installation.
+fn print_plan(args: &Args, plan: &[Action]) {
+	if args.json {
+		match serde_json::to_string_pretty(plan) {
+			Ok(json) => println!("{json}"),
+			Err(e) => fatal(&format!("Cannot serialize plan: {e}")),
+		}
+		return;
+	}
+
+	println!("The installation performs the following actions:");
+	for (i, action) in plan.iter().enumerate() {
+		println!("{:>3}. {action}", i + 1);
+		if let Action::WritePartitionTable {
+			partitions, ..
+		} = action
+		{
+			for p in partitions {
+				println!("\t- {p}");
+			}
+		}
+	}
+}
+
+/// Performs an interactive installation with the given frontend.
+fn install(args: &Args, frontend: &Frontend) {
+	let confirm = !args.yes;
+	let infos = InstallInfo {
+		bootloader: args.bootloader,
+		efi_removable: args.efi_removable,
+		..Default::default()
+	};
+	let mut prompt: Box<dyn InstallPrompt> = match frontend {
+		Frontend::Term => Box::new(DialogPrompt::new(TermDialog::default(), infos, confirm)),
+		Frontend::Tui => match Tui::new() {
+			Ok(tui) => Box::new(DialogPrompt::new(tui, infos, confirm)),
+			Err(e) => fatal(&format!("Cannot initialize terminal: {e}")),
+		},
+		Frontend::Gui => {
+			let gui = DevFramebuffer::open(Path::new("/dev/fb0")).and_then(Gui::new);
+			match gui {
+				Ok(gui) => Box::new(DialogPrompt::new(gui, infos, confirm)),
+				Err(e) => fatal(&format!("Cannot initialize framebuffer: {e}")),
+			}
+		}
+		Frontend::GuiSnapshot(dir) => {
+			let fb = MemFramebuffer::new(800, 600, Some(dir.clone()));
+			Box::new(DialogPrompt::new(Gui::headless(fb), infos, confirm))
+		}
+	};
+
+	let mut log_file = open_log_file(args);
 	while let Some(curr_step) = prompt.get_current_step() {
 		prompt.next_step();
-		if matches!(curr_step, InstallStep::Install) {
+		// The user may have gone back from the confirmation
+		let confirmed = matches!(curr_step, InstallStep::Install)
+			&& matches!(prompt.get_current_step(), Some(InstallStep::Finished));
+		if confirmed {
 			let infos = prompt.get_infos();
-			if let Err(e) = infos.perform_install() {
-				eprintln!("{CODE_RED}Installation failed: {e}{CODE_RESET}");
-				exit(1);
+			if args.dry_run {
+				// Give the terminal back before printing the plan
+				drop(prompt);
+				print_plan(args, &install_plan(args, &infos));
+				return;
+			}
+			let plan = match infos.plan(&args.target_root) {
+				Ok(plan) => plan,
+				Err(e) => {
+					// Nothing has been performed, so there is nothing to resume
+					drop(prompt);
+					fatal(&format!("Cannot plan installation: {e}"));
+				}
+			};
+			let result = {
+				let mut progress = new_progress(log_file.take(), |e| prompt.progress(e));
+				infos.perform_install(&plan, &exec_options(args), &mut progress)
+			};
+			if let Err(e) = result {
+				// Give the terminal back, then print the error again since the screen showing it
+				// is gone
+				drop(prompt);
+				eprintln!("{e}");
+				install_failed(args);
 			}
 		}
 	}
 }
+
+/// Performs an unattended installation using the answer file at the given path.
+fn auto_install(args: &Args, path: &Path) {
+	let infos = load_answer_file(args, path);
+	let plan = install_plan(args, &infos);
+	if args.dry_run {
+		print_plan(args, &plan);
+		return;
+	}
+	let mut progress = new_progress(open_log_file(args), print_event);
+	if infos
+		.perform_install(&plan, &exec_options(args), &mut progress)
+		.is_err()
+	{
+		install_failed(args);
+	}
+	print_finished("Installation is now finished!");
+}
+
+/// Resumes an interrupted installation from its recorded state.
+fn resume(args: &Args) {
+	// The parser requires the state file for this command
+	let state_file = args.state_file.as_deref().unwrap_or_else(|| unreachable!());
+	let state = InstallState::load(state_file).unwrap_or_else(|e| {
+		fatal(&format!(
+			"Cannot read installation state `{}`: {e}",
+			state_file.display()
+		))
+	});
+	if args.dry_run {
+		print_plan(args, state.plan.get(state.completed..).unwrap_or_default());
+		return;
+	}
+	let mut progress = new_progress(open_log_file(args), print_event);
+	if InstallInfo::resume(&state, &exec_options(args), &mut progress).is_err() {
+		install_failed(args);
+	}
+	print_finished("Installation is now finished!");
+}
+
+/// Prints the actions an installation from the answer file at the given path would perform.
+fn plan(args: &Args, path: &Path) {
+	let infos = load_answer_file(args, path);
+	print_plan(args, &install_plan(args, &infos));
+}
+
+/// Prints the list of disks the system can be installed on.
+fn list_disks() {
+	let disks =
+		Disk::list().unwrap_or_else(|e| fatal(&format!("Failed to retrieve disks list: {e}")));
+	for dev_path in disks {
+		let disk = match Disk::read(dev_path.clone()) {
+			Ok(Some(disk)) => disk,
+			Ok(None) => continue,
+			Err(e) => fatal(&format!("Cannot read disk: {e}")),
+		};
+		println!(
+			"- {} (sectors: {}, size: {})",
+			dev_path.display(),
+			disk.size(),
+			ByteSize(disk.size()),
+		);
+		for p in &disk.partition_table.partitions {
+			println!("\t- {p}");
+		}
+	}
+}
+
+/// Prints the list of available languages.
+fn list_languages() {
+	let langs =
+		Language::list().unwrap_or_else(|e| fatal(&format!("Cannot read languages list: {e}")));
+	let mut langs: Vec<_> = langs.into_values().map(|l| l.to_string()).collect();
+	langs.sort_unstable();
+	for lang in langs {
+		println!("{lang}");
+	}
+}
+
+/// Reinstalls the bootloader of a system installed from the answer file at the given path.
+fn repair(args: &Args, path: &Path) {
+	let infos = load_answer_file(args, path);
+	if !args.yes {
+		let question = format!(
+			"Reinstall the bootloader on `{}`? (y/n) ",
+			infos.selected_disk.display()
+		);
+		if !prompt_yes_no(&question) {
+			fatal("Repair cancelled");
+		}
+	}
+
+	let mut progress = new_progress(open_log_file(args), print_event);
+	// The error is reported by the progress of the repair
+	if infos.repair(&args.target_root, &mut progress).is_err() {
+		if !args.json {
+			eprintln!("{CODE_RED}Repair failed{CODE_RESET}");
+		}
+		exit(1);
+	}
+}
+
+fn main() {
+	let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+		eprintln!("{e}");
+		eprintln!("Try `install --help` for more information.");
+		exit(1);
+	});
+	JSON_OUTPUT.store(args.json, Ordering::Relaxed);
+
+	match &args.command {
+		Command::Install(frontend) => install(&args, frontend),
+		Command::Auto(path) => auto_install(&args, path),
+		Command::Validate(path) => {
+			load_answer_file(&args, path);
+			println!("Answer file `{}` is valid", path.display());
+		}
+		Command::Plan(path) => plan(&args, path),
+		Command::ListDisks => list_disks(),
+		Command::ListLanguages => list_languages(),
+		Command::Repair(path) => repair(&args, path),
+		Command::Resume => resume(&args),
+		Command::Help => println!("{USAGE}"),
+		Command::Version => println!("install {}", env!("CARGO_PKG_VERSION")),
+	}
+}
@@ -0,0 +1,648 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements installation prompt on top of a user interface made of dialogs.
+//!
+//! A user interface only has to provide a few kinds of dialogs (menus, text inputs, messages...).
+//! The installation steps are implemented once for all of them.
+
+use super::{
+	non_empty_validator, size_validator, summary, InstallPrompt, InstallStep, StepCursor,
+	SwapKind, MIB_SECTORS,
+};
+use crate::{
+	hw::Hardware,
+	install::{
+		bootloader::Bootloader, check_hostname, dualboot, filesystem::Filesystem, grub,
+		partition_type_name, progress::Event, InstallInfo, PartitionDesc, SECTOR_SIZE,
+	},
+	lang::Language,
+	util,
+};
+use common::{
+	maestro_utils,
+	maestro_utils::{disk::Disk, util::ByteSize},
+};
+use std::{path::Path, process::exit};
+
+/// An entry of a menu.
+pub struct MenuItem {
+	/// The text of the entry.
+	pub label: String,
+	/// Lines of details shown while the entry is highlighted.
+	pub details: Vec<String>,
+}
+
+impl MenuItem {
+	/// Creates an entry without details.
+	pub fn new(label: impl Into<String>) -> Self {
+		Self {
+			label: label.into(),
+			details: vec![],
+		}
+	}
+}
+
+/// A user interface made of dialogs.
+pub trait Dialog {
+	/// Shows the welcome screen, then waits for the user to continue.
+	fn welcome(&mut self);
+	/// Shows a menu and returns the index of the item selected by the user.
+	///
+	/// `text` is shown above the items.
+	///
+	/// If the user asked to go back, the function returns `None`.
+	fn menu(&mut self, title: &str, text: &str, items: &[MenuItem]) -> Option<usize>;
+	/// Prompts text from the user.
+	///
+	/// Arguments:
+	/// - `label` is the text showed next to the input field.
+	/// - `hidden` tells whether the input must be hidden.
+	/// - `validator` is a function called to check whether the given input is valid, with the same
+	///   semantics as for the terminal prompt.
+	///
+	/// If the user asked to go back, the function returns `None`.
+	fn input(
+		&mut self,
+		title: &str,
+		label: &str,
+		hidden: bool,
+		validator: &dyn Fn(&str) -> Result<(), Option<String>>,
+	) -> Option<String>;
+	/// Shows the given lines of text, then waits for the user to continue.
+	fn message(&mut self, title: &str, lines: &[String]);
+	/// Appends a line to the installation progress pane.
+	fn progress(&mut self, line: &str);
+	/// Updates the overall progress of the installation shown in the progress pane, in percent.
+	fn set_percent(&mut self, percent: u8);
+
+	/// Gives the screen back, restoring its state from before the dialog was created.
+	fn close(&mut self);
+}
+
+/// Returns the lines of a table showing the given partitions.
+pub fn partitions_table(partitions: &[PartitionDesc]) -> Vec<String> {
+	let mut lines = vec![format!(
+		"{:<3} {:>12} {:>12} {:<20} {:<6} {:<5} {}",
+		"#", "Start", "Sectors", "Type", "FS", "Boot", "Mount"
+	)];
+	for (i, p) in partitions.iter().enumerate() {
+		let mount_path = p
+			.mount_path
+			.as_ref()
+			.map(|p| p.display().to_string())
+			.unwrap_or_default();
+		lines.push(format!(
+			"{:<3} {:>12} {:>12} {:<20} {:<6} {:<5} {mount_path}",
+			i + 1,
+			p.start,
+			p.size,
+			p.type_name(),
+			p.get_filesystem().to_string(),
+			if p.bootable { "yes" } else { "" },
+		));
+	}
+	lines
+}
+
+/// Installation prompt working with any user interface made of dialogs.
+pub struct DialogPrompt<D: Dialog> {
+	/// The user interface.
+	dialog: D,
+
+	/// The current step.
+	steps: StepCursor,
+
+	/// Install informations.
+	infos: InstallInfo,
+	/// The hardware of the machine.
+	hardware: Hardware,
+
+	/// Tells whether the user has to confirm the installation.
+	confirm: bool,
+}
+
+impl<D: Dialog> DialogPrompt<D> {
+	/// Creates a new instance with the given user interface.
+	///
+	/// `infos` is the information to start from, such as options given on the command line.
+	///
+	/// If `confirm` is `false`, the installation begins without asking for confirmation.
+	pub fn new(dialog: D, infos: InstallInfo, confirm: bool) -> Self {
+		Self {
+			dialog,
+
+			steps: StepCursor::new(),
+
+			infos,
+			hardware: Hardware::detect(),
+
+			confirm,
+		}
+	}
+
+	/// Shows the given error, then exits the program.
+	fn fatal(&mut self, msg: &str) -> ! {
+		self.dialog.message("Error", &[msg.to_owned()]);
+		self.dialog.close();
+		eprintln!("{msg}");
+		exit(1);
+	}
+
+	/// Prompts the system's language.
+	fn localization(&mut self, title: &str) -> Option<()> {
+		let mut langs: Vec<Language> = match Language::list() {
+			Ok(l) => l.into_values().collect(),
+			Err(e) => panic!("Could not read languages list. This is a bug. Error: {e}"),
+		};
+		if langs.is_empty() {
+			self.fatal("No language is available. This is a bug.");
+		}
+		langs.sort_by_cached_key(Language::to_string);
+
+		let items: Vec<MenuItem> = langs.iter().map(|l| MenuItem::new(l.to_string())).collect();
+		let i = self
+			.dialog
+			.menu(title, "Select the system's language:", &items)?;
+		self.infos.lang = Some(langs.swap_remove(i));
+
+		// TODO Contient/Country
+		// TODO Timezone
+		Some(())
+	}
+
+	/// Prompts the admin user's credentials.
+	fn create_admin(&mut self, title: &str) -> Option<()> {
+		let admin_user =
+			self.dialog
+				.input(title, "Admin username:", false, &non_empty_validator)?;
+
+		loop {
+			let pass =
+				self.dialog
+					.input(title, "Admin/root password:", true, &non_empty_validator)?;
+			let confirmed =
+				self.dialog
+					.input(title, "Confirm admin/root password:", true, &|input| {
+						if input == pass {
+							Ok(())
+						} else {
+							Err(Some("Passwords don't match!".to_owned()))
+						}
+					});
+			// Going back from the confirmation prompts the password again
+			if confirmed.is_none() {
+				continue;
+			}
+
+			match maestro_utils::user::hash_password(&pass) {
+				Ok(pass) => {
+					self.infos.admin_user = admin_user;
+					self.infos.admin_pass = pass;
+					break Some(());
+				}
+				Err(e) => self
+					.dialog
+					.message(title, &[format!("Invalid password: {e}")]),
+			}
+		}
+	}
+
+	/// Shows the detected hardware, along with warnings about unsupported configurations.
+	fn hardware(&mut self, title: &str) -> Option<()> {
+		// Detect again in case a device has been plugged in the meantime
+		self.hardware = Hardware::detect();
+		let mut lines = self.hardware.report();
+		let warnings = self.hardware.warnings();
+		if !warnings.is_empty() {
+			lines.push(String::new());
+		}
+		lines.extend(warnings.iter().map(|w| format!("Warning: {w}")));
+		self.dialog
+			.menu(title, &lines.join("\n"), &[MenuItem::new("Continue")])?;
+		Some(())
+	}
+
+	/// Prompts the disk and partitions to install the system on.
+	fn partitions(&mut self, title: &str) -> Option<()> {
+		let dev_paths = Disk::list()
+			.unwrap_or_else(|e| self.fatal(&format!("Failed to retrieve disks list: {e}")));
+
+		let mut disks = vec![];
+		let mut items = vec![];
+		for dev_path in dev_paths {
+			let disk = match Disk::read(dev_path.clone()) {
+				Ok(Some(disk)) => disk,
+				Ok(None) => continue,
+				Err(e) => self.fatal(&format!("Cannot read disk: {e}")),
+			};
+
+			let mut details: Vec<String> = disk
+				.partition_table
+				.partitions
+				.iter()
+				.map(ToString::to_string)
+				.collect();
+			if details.is_empty() {
+				details.push("No partition".to_owned());
+			}
+			let mut label = format!(
+				"{} (sectors: {}, size: {})",
+				dev_path.display(),
+				disk.size(),
+				ByteSize(disk.size())
+			);
+			if let Some(info) = self.hardware.disk(&dev_path) {
+				if !info.model.is_empty() {
+					label = format!("{label} {}", info.model);
+				}
+			}
+			items.push(MenuItem {
+				label,
+				details,
+			});
+			disks.push((dev_path, disk));
+		}
+		// TODO Filter out disks that don't have enough space
+		if disks.is_empty() {
+			self.fatal("No disk is available for installation. Exiting...");
+		}
+
+		loop {
+			let i =
+				self.dialog
+					.menu(title, "Select the disk to install the system on:", &items)?;
+			let (dev_path, disk) = &disks[i];
+			if self.partitioning(title, dev_path, disk.size()).is_some() {
+				break Some(());
+			}
+			// Going back from the partitioning selects another disk
+		}
+	}
+
+	/// Prompts the bootloader and partitions of the disk selected previously.
+	fn bootloader(&mut self, title: &str) -> Option<()> {
+		let dev_path = self.infos.selected_disk.clone();
+		let disk = match Disk::read(dev_path.clone()) {
+			Ok(Some(disk)) => disk,
+			Ok(None) => self.fatal(&format!("Cannot read disk `{}`", dev_path.display())),
+			Err(e) => self.fatal(&format!("Cannot read disk: {e}")),
+		};
+		self.partitioning(title, &dev_path, disk.size())
+	}
+
+	/// Prompts how to partition the disk at `dev_path`, of `disk_size` sectors.
+	fn partitioning(&mut self, title: &str, dev_path: &Path, disk_size: u64) -> Option<()> {
+		let default_bootloader = self
+			.infos
+			.bootloader
+			.unwrap_or_else(|| Bootloader::default_for(self.hardware.firmware));
+		let bootloaders: Vec<MenuItem> = Bootloader::ALL
+			.iter()
+			.map(|b| {
+				let label = if *b == default_bootloader {
+					format!("{b} (recommended)")
+				} else {
+					b.to_string()
+				};
+				let required: Vec<String> = b
+					.required_partitions()
+					.into_iter()
+					.map(|t| format!("Requires a {} partition", partition_type_name(t)))
+					.collect();
+				MenuItem {
+					label,
+					details: required,
+				}
+			})
+			.collect();
+		let swap_kinds: Vec<MenuItem> = SwapKind::ALL
+			.iter()
+			.map(|k| MenuItem::new(k.to_string()))
+			.collect();
+
+		let text = format!(
+			"Installing system on disk `{}`\nPartitioning options:",
+			dev_path.display()
+		);
+		let options = [MenuItem {
+			label: "Wipe disk and install system automatically".to_owned(),
+			details: vec!["Warning: this operation will destroy all data on the disk".to_owned()],
+		}];
+		// TODO manual partitioning and use of free space left on disk
+		match self.dialog.menu(title, &text, &options)? {
+			0 => {}
+			_ => unreachable!(),
+		}
+		let b = self
+			.dialog
+			.menu(title, "Select the bootloader:", &bootloaders)?;
+		let bootloader = Bootloader::ALL[b];
+		let s = self
+			.dialog
+			.menu(title, "Select the kind of swap space:", &swap_kinds)?;
+		let swap = SwapKind::ALL[s];
+		let mut swap_size = 0;
+		if swap != SwapKind::None {
+			let default_swap = PartitionDesc::default_swap_size(disk_size, &self.hardware);
+			let label = format!(
+				"Swap size in MiB (leave empty for {}, 0 for none):",
+				default_swap / MIB_SECTORS
+			);
+			let size = self.dialog.input(title, &label, false, &size_validator)?;
+			swap_size = size
+				.parse::<u64>()
+				.map(|mib| mib * MIB_SECTORS)
+				.unwrap_or(default_swap);
+		}
+
+		let partitions = PartitionDesc::auto_layout(
+			disk_size,
+			&self.hardware,
+			bootloader,
+			if swap == SwapKind::Partition {
+				swap_size
+			} else {
+				0
+			},
+		);
+		let partitions = match partitions {
+			Ok(partitions) => partitions,
+			Err(e) => {
+				self.dialog
+					.message(title, &[format!("Cannot partition the disk: {e}")]);
+				return None;
+			}
+		};
+		self.infos.selected_disk = dev_path.to_path_buf();
+		self.infos.bootloader = Some(bootloader);
+		self.infos.partitions = partitions;
+		self.infos.swap_file_size =
+			(swap == SwapKind::File && swap_size > 0).then_some(swap_size * SECTOR_SIZE);
+
+		self.filesystems(title)
+	}
+
+	/// Shows the partitions to be created and lets the user change their filesystems.
+	fn filesystems(&mut self, title: &str) -> Option<()> {
+		let filesystems: Vec<MenuItem> = Filesystem::ALL
+			.iter()
+			.map(|f| MenuItem::new(f.to_string()))
+			.collect();
+		loop {
+			let mut table = partitions_table(&self.infos.partitions).into_iter();
+			let text = format!(
+				"The following partitions will be created. Select a partition to change its \
+				 filesystem:\n\n    {}",
+				table.next().unwrap()
+			);
+			let mut items: Vec<MenuItem> = table.map(MenuItem::new).collect();
+			items.push(MenuItem::new("Continue"));
+			let i = self.dialog.menu(title, &text, &items)?;
+			if i >= self.infos.partitions.len() {
+				break Some(());
+			}
+			let text = format!("Select the filesystem of partition {}:", i + 1);
+			let Some(f) = self.dialog.menu(title, &text, &filesystems) else {
+				continue;
+			};
+			let part = &mut self.infos.partitions[i];
+			let prev = part.filesystem.replace(Filesystem::ALL[f]);
+			if let Err(e) = part.check_filesystem() {
+				part.filesystem = prev;
+				self.dialog
+					.message(title, &[format!("Invalid filesystem: {e}")]);
+			}
+		}
+	}
+
+	/// Prompts the arguments passed to the kernel of the installed system.
+	fn boot_options(&mut self, title: &str) -> Option<()> {
+		let live_args = grub::live_kernel_args();
+		let mut kernel_args = vec![];
+		if !live_args.is_empty() {
+			let text = format!(
+				"Kernel arguments of the installation medium:\n{}\nCarry them over to the installed system?",
+				live_args.join(" ")
+			);
+			if self
+				.dialog
+				.menu(title, &text, &[MenuItem::new("No"), MenuItem::new("Yes")])?
+				== 1
+			{
+				kernel_args = live_args;
+			}
+		}
+		let args = self.dialog.input(
+			title,
+			"Additional kernel arguments (leave empty for none):",
+			false,
+			&|input| {
+				input
+					.split_whitespace()
+					.try_for_each(grub::check_kernel_arg)
+					.map_err(|e| Some(format!("Invalid arguments: {e}")))
+			},
+		)?;
+		kernel_args.extend(args.split_whitespace().map(str::to_owned));
+
+		let mut other_systems = vec![];
+		if self.infos.bootloader != Some(Bootloader::None) {
+			let (systems, warnings) = dualboot::scan(&self.infos.selected_disk);
+			if !warnings.is_empty() {
+				self.dialog.message(title, &warnings);
+			}
+			if !systems.is_empty() {
+				let mut text = "Other operating systems have been found:\n".to_owned();
+				for system in &systems {
+					text.push_str(&format!("- {system}\n"));
+				}
+				text.push_str("Add them to the boot menu?");
+				if self
+					.dialog
+					.menu(title, &text, &[MenuItem::new("No"), MenuItem::new("Yes")])?
+					== 1
+				{
+					other_systems = systems;
+				}
+			}
+		}
+
+		self.infos.kernel_args = kernel_args;
+		self.infos.other_systems = other_systems;
+		Some(())
+	}
+
+	/// Shows the summary of collected information, allowing to edit it.
+	fn summary(&mut self, title: &str) -> Option<()> {
+		let entries = summary(&self.infos);
+		let mut items: Vec<MenuItem> = entries
+			.iter()
+			.map(|entry| MenuItem {
+				label: entry.text.clone(),
+				details: entry.details.clone(),
+			})
+			.collect();
+		items.push(MenuItem::new("Continue"));
+
+		let i = self.dialog.menu(
+			title,
+			"The system is about to be installed with the following options.\nSelect an option to edit it:",
+			&items,
+		)?;
+		match entries.get(i) {
+			Some(entry) => self.steps.edit(entry.step),
+			None => match self.infos.validate() {
+				Ok(()) => self.steps.next(),
+				// Stay on the summary until the information is complete
+				Err(e) => self
+					.dialog
+					.message(title, &[format!("Cannot continue: {e}")]),
+			},
+		}
+		Some(())
+	}
+
+	/// Asks for the confirmation of the installation.
+	fn confirm(&mut self, title: &str) -> Option<()> {
+		let items = [
+			MenuItem::new("Install"),
+			MenuItem::new("Export selected options to an answer file"),
+			MenuItem::new("Cancel installation"),
+		];
+		loop {
+			match self.dialog.menu(title, "Confirm installation?", &items)? {
+				0 => break Some(()),
+				1 => {
+					let Some(path) =
+						self.dialog
+							.input(title, "Answer file path:", false, &non_empty_validator)
+					else {
+						continue;
+					};
+					let Some(with_pass) = self.dialog.menu(
+						title,
+						"Include the admin password hash?",
+						&[MenuItem::new("No"), MenuItem::new("Yes")],
+					) else {
+						continue;
+					};
+					let with_pass = with_pass == 1;
+					let msg = match self.infos.save(Path::new(&path), with_pass) {
+						Ok(()) => format!("Selected options exported to `{path}`"),
+						Err(e) => format!("Cannot export selected options: {e}"),
+					};
+					self.dialog.message(title, &[msg]);
+				}
+				_ => {
+					self.dialog.close();
+					eprintln!("Installation cancelled");
+					exit(1);
+				}
+			}
+		}
+	}
+}
+
+impl<D: Dialog> InstallPrompt for DialogPrompt<D> {
+	fn get_current_step(&self) -> Option<InstallStep> {
+		self.steps.get()
+	}
+
+	fn next_step(&mut self) {
+		let Some(curr_step) = self.steps.get() else {
+			return;
+		};
+		let title = match curr_step.get_name() {
+			Some(name) => format!("Step {}: {name}", curr_step.get_number()),
+			None => String::new(),
+		};
+
+		let res = match curr_step {
+			InstallStep::Welcome => {
+				self.dialog.welcome();
+				Some(())
+			}
+
+			InstallStep::Localization => self.localization(&title),
+
+			InstallStep::SystemInfo => self
+				.dialog
+				.input(&title, "System hostname:", false, &|input| {
+					non_empty_validator(input)?;
+					check_hostname(input).map_err(Some)
+				})
+				.map(|hostname| self.infos.hostname = hostname),
+
+			InstallStep::CreateAdmin => self.create_admin(&title),
+
+			InstallStep::Hardware => self.hardware(&title),
+
+			InstallStep::Partitions => self.partitions(&title),
+			InstallStep::Bootloader => self.bootloader(&title),
+
+			InstallStep::BootOptions => self.boot_options(&title),
+
+			// The summary moves to the next step by itself
+			InstallStep::Summary => match self.summary(&title) {
+				Some(()) => return,
+				None => None,
+			},
+
+			InstallStep::Install if self.confirm => self.confirm(&title),
+			InstallStep::Install => Some(()),
+
+			InstallStep::Finished => {
+				self.dialog.message(
+					&title,
+					&[
+						"Installation is now finished!".to_owned(),
+						"To start maestro, unplug your installation medium, then press ENTER"
+							.to_owned(),
+					],
+				);
+				self.dialog.close();
+				util::reboot();
+			}
+		};
+
+		match res {
+			Some(()) => self.steps.next(),
+			None => self.steps.previous(),
+		}
+	}
+
+	fn get_infos(&self) -> InstallInfo {
+		self.infos.clone()
+	}
+
+	fn progress(&mut self, event: &Event) {
+		if let Event::Progress {
+			percent,
+		} = event
+		{
+			self.dialog.set_percent(*percent);
+		}
+		let Some(text) = event.text() else {
+			return;
+		};
+		for line in text.lines().filter(|l| !l.is_empty()) {
+			self.dialog.progress(line);
+		}
+	}
+}
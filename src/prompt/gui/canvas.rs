@@ -0,0 +1,126 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements drawing primitives on a buffer of pixels.
+
+use super::font;
+
+/// A buffer of pixels to draw on.
+///
+/// Pixels are stored row by row, in the `0xRRGGBB` format.
+pub struct Canvas {
+	/// The width in pixels.
+	width: usize,
+	/// The height in pixels.
+	height: usize,
+
+	/// The pixels.
+	pixels: Vec<u32>,
+}
+
+impl Canvas {
+	/// Creates a black canvas with the given size in pixels.
+	pub fn new(width: usize, height: usize) -> Self {
+		Self {
+			width,
+			height,
+
+			pixels: vec![0; width * height],
+		}
+	}
+
+	/// Returns the width and height of the canvas, in pixels.
+	pub fn size(&self) -> (usize, usize) {
+		(self.width, self.height)
+	}
+
+	/// Returns the pixels of the canvas.
+	pub fn pixels(&self) -> &[u32] {
+		&self.pixels
+	}
+
+	/// Fills the whole canvas with the given color.
+	pub fn fill(&mut self, color: u32) {
+		self.pixels.fill(color);
+	}
+
+	/// Fills a rectangle with the given color.
+	///
+	/// Parts of the rectangle outside of the canvas are ignored.
+	pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u32) {
+		let x_end = (x + width).min(self.width);
+		let y_end = (y + height).min(self.height);
+		if x >= x_end {
+			return;
+		}
+		for row in y..y_end {
+			let off = row * self.width;
+			self.pixels[off + x..off + x_end].fill(color);
+		}
+	}
+
+	/// Draws the outline of a rectangle with the given color and line thickness.
+	pub fn stroke_rect(
+		&mut self,
+		x: usize,
+		y: usize,
+		width: usize,
+		height: usize,
+		thickness: usize,
+		color: u32,
+	) {
+		self.fill_rect(x, y, width, thickness, color);
+		self.fill_rect(
+			x,
+			(y + height).saturating_sub(thickness),
+			width,
+			thickness,
+			color,
+		);
+		self.fill_rect(x, y, thickness, height, color);
+		self.fill_rect(
+			(x + width).saturating_sub(thickness),
+			y,
+			thickness,
+			height,
+			color,
+		);
+	}
+
+	/// Draws a character with its top-left corner at the given position.
+	///
+	/// `scale` is the size of the pixels of the glyph.
+	pub fn draw_char(&mut self, x: usize, y: usize, c: char, color: u32, scale: usize) {
+		for (row, bits) in font::glyph(c).iter().enumerate() {
+			for col in 0..font::WIDTH {
+				if bits & (0x80 >> col) != 0 {
+					self.fill_rect(x + col * scale, y + row * scale, scale, scale, color);
+				}
+			}
+		}
+	}
+
+	/// Draws text on a single line, with its top-left corner at the given position.
+	///
+	/// `scale` is the size of the pixels of glyphs.
+	pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+		for (i, c) in text.chars().enumerate() {
+			self.draw_char(x + i * font::WIDTH * scale, y, c, color, scale);
+		}
+	}
+}
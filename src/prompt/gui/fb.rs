@@ -0,0 +1,197 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements the framebuffers on which the graphical interface is shown.
+
+use std::{
+	fs,
+	fs::{File, OpenOptions},
+	io,
+	io::{Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+};
+
+/// A buffer of pixels shown to the user.
+pub trait Framebuffer {
+	/// Returns the width and height of the framebuffer, in pixels.
+	fn size(&self) -> (usize, usize);
+	/// Shows the given pixels.
+	///
+	/// Pixels are given row by row, in the `0xRRGGBB` format. There are exactly as many as
+	/// required by the size of the framebuffer.
+	fn present(&mut self, pixels: &[u32]) -> io::Result<()>;
+}
+
+/// Reads the attribute of a framebuffer device from sysfs and parses it.
+fn read_attr(sysfs_path: &Path, name: &str) -> io::Result<String> {
+	let content = fs::read_to_string(sysfs_path.join(name))?;
+	Ok(content.trim().to_owned())
+}
+
+/// Parses an integer attribute of a framebuffer device.
+fn parse_attr(value: &str) -> io::Result<usize> {
+	value
+		.parse()
+		.map_err(|_| io::Error::other(format!("invalid framebuffer attribute `{value}`")))
+}
+
+/// A framebuffer device, such as `/dev/fb0`.
+pub struct DevFramebuffer {
+	/// The device file.
+	file: File,
+
+	/// The width in pixels.
+	width: usize,
+	/// The height in pixels.
+	height: usize,
+	/// The number of bits per pixel.
+	bpp: usize,
+	/// The number of bytes per line.
+	stride: usize,
+}
+
+impl DevFramebuffer {
+	/// Opens the framebuffer device at the given path.
+	///
+	/// The geometry of the framebuffer is read from sysfs.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let name = path
+			.file_name()
+			.ok_or_else(|| io::Error::other("invalid framebuffer device path"))?;
+		let sysfs_path = PathBuf::from("/sys/class/graphics").join(name);
+
+		let size = read_attr(&sysfs_path, "virtual_size")?;
+		let (width, height) = size
+			.split_once(',')
+			.ok_or_else(|| io::Error::other(format!("invalid framebuffer size `{size}`")))?;
+		let width = parse_attr(width)?;
+		let height = parse_attr(height)?;
+		let bpp = parse_attr(&read_attr(&sysfs_path, "bits_per_pixel")?)?;
+		if !matches!(bpp, 16 | 24 | 32) {
+			return Err(io::Error::other(format!(
+				"unsupported framebuffer pixel depth: {bpp} bits"
+			)));
+		}
+		let stride = match read_attr(&sysfs_path, "stride") {
+			Ok(stride) => parse_attr(&stride)?,
+			Err(_) => width * bpp / 8,
+		};
+
+		let file = OpenOptions::new().write(true).open(path)?;
+		Ok(Self {
+			file,
+
+			width,
+			height,
+			bpp,
+			stride,
+		})
+	}
+}
+
+impl Framebuffer for DevFramebuffer {
+	fn size(&self) -> (usize, usize) {
+		(self.width, self.height)
+	}
+
+	fn present(&mut self, pixels: &[u32]) -> io::Result<()> {
+		let mut buf = vec![0u8; self.stride * self.height];
+		for (line, row) in buf
+			.chunks_exact_mut(self.stride)
+			.zip(pixels.chunks(self.width))
+		{
+			for (x, pixel) in row.iter().enumerate() {
+				let [b, g, r, _] = pixel.to_le_bytes();
+				match self.bpp {
+					32 => line[x * 4..x * 4 + 4].copy_from_slice(&[b, g, r, 0]),
+					24 => line[x * 3..x * 3 + 3].copy_from_slice(&[b, g, r]),
+					// RGB565
+					_ => {
+						let pixel =
+							((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3);
+						line[x * 2..x * 2 + 2].copy_from_slice(&pixel.to_le_bytes());
+					}
+				}
+			}
+		}
+		self.file.seek(SeekFrom::Start(0))?;
+		self.file.write_all(&buf)
+	}
+}
+
+/// A framebuffer kept in memory, allowing to render the interface without a display.
+///
+/// Each presented frame can be saved as a PPM image, to check how the interface looks.
+pub struct MemFramebuffer {
+	/// The width in pixels.
+	width: usize,
+	/// The height in pixels.
+	height: usize,
+
+	/// The last presented pixels.
+	pixels: Vec<u32>,
+
+	/// The directory in which frames are saved. If `None`, frames are not saved.
+	snapshot_dir: Option<PathBuf>,
+	/// The number of presented frames.
+	frames_count: usize,
+}
+
+impl MemFramebuffer {
+	/// Creates a new framebuffer with the given size in pixels.
+	///
+	/// If `snapshot_dir` is set, each presented frame is saved in this directory, with the name
+	/// `frame-<n>.ppm`, where `n` is the number of the frame.
+	pub fn new(width: usize, height: usize, snapshot_dir: Option<PathBuf>) -> Self {
+		Self {
+			width,
+			height,
+
+			pixels: vec![0; width * height],
+
+			snapshot_dir,
+			frames_count: 0,
+		}
+	}
+
+	/// Encodes the last presented pixels as a binary PPM image.
+	pub fn to_ppm(&self) -> Vec<u8> {
+		let mut buf = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+		for pixel in &self.pixels {
+			let [b, g, r, _] = pixel.to_le_bytes();
+			buf.extend_from_slice(&[r, g, b]);
+		}
+		buf
+	}
+}
+
+impl Framebuffer for MemFramebuffer {
+	fn size(&self) -> (usize, usize) {
+		(self.width, self.height)
+	}
+
+	fn present(&mut self, pixels: &[u32]) -> io::Result<()> {
+		self.pixels.copy_from_slice(pixels);
+		if let Some(dir) = &self.snapshot_dir {
+			let path = dir.join(format!("frame-{:04}.ppm", self.frames_count));
+			fs::write(path, self.to_ppm())?;
+		}
+		self.frames_count += 1;
+		Ok(())
+	}
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module renders the welcome message, including the logo, as an image.
+//!
+//! The message is the same as the one of the terminal prompt. Its ANSI color codes are interpreted
+//! to draw it.
+
+use super::{canvas::Canvas, font};
+
+/// The welcome message, with ANSI color codes.
+const MOTD: &str = include_str!("../motd");
+
+/// The default color of the text.
+const DEFAULT_COLOR: u32 = 0xc0c0c0;
+
+/// Returns the color corresponding to the given ANSI color code, if any.
+fn ansi_color(code: u32) -> Option<u32> {
+	let color = match code {
+		30 => 0x000000,
+		31 => 0xcd0000,
+		32 => 0x00cd00,
+		33 => 0xcdcd00,
+		34 => 0x0000ee,
+		35 => 0xcd00cd,
+		36 => 0x00cdcd,
+		37 => 0xe5e5e5,
+		90 => 0x7f7f7f,
+		91 => 0xff0000,
+		92 => 0x00ff00,
+		93 => 0xffff00,
+		94 => 0x5c5cff,
+		95 => 0xff00ff,
+		96 => 0x00ffff,
+		97 => 0xffffff,
+		_ => return None,
+	};
+	Some(color)
+}
+
+/// Returns the size of the rendered message, in pixels, for the given scale.
+pub fn size(scale: usize) -> (usize, usize) {
+	let mut cols = 0;
+	let mut rows = 0;
+	for line in MOTD.lines() {
+		let mut chars = line.chars();
+		let mut len = 0;
+		while let Some(c) = chars.next() {
+			if c == '\x1b' {
+				// Skip the escape sequence
+				for c in chars.by_ref() {
+					if c == 'm' {
+						break;
+					}
+				}
+			} else {
+				len += 1;
+			}
+		}
+		cols = cols.max(len);
+		rows += 1;
+	}
+	(cols * font::WIDTH * scale, rows * font::HEIGHT * scale)
+}
+
+/// Draws the message with its top-left corner at the given position.
+///
+/// `scale` is the size of the pixels of glyphs.
+pub fn draw(canvas: &mut Canvas, x: usize, y: usize, scale: usize) {
+	let cell_width = font::WIDTH * scale;
+	let cell_height = font::HEIGHT * scale;
+
+	let mut fg = DEFAULT_COLOR;
+	let mut bg = None;
+	for (row, line) in MOTD.lines().enumerate() {
+		let mut col = 0;
+		let mut chars = line.chars();
+		while let Some(c) = chars.next() {
+			if c == '\x1b' {
+				// Apply Select Graphic Rendition parameters
+				let seq: String = chars.by_ref().take_while(|c| *c != 'm').collect();
+				for code in seq.trim_start_matches('[').split(';') {
+					match code.parse::<u32>() {
+						Ok(0) => {
+							fg = DEFAULT_COLOR;
+							bg = None;
+						}
+						Ok(39) => fg = DEFAULT_COLOR,
+						Ok(49) => bg = None,
+						Ok(code @ 40..=47) => bg = ansi_color(code - 10),
+						Ok(code @ 100..=107) => bg = ansi_color(code - 10),
+						Ok(code) => fg = ansi_color(code).unwrap_or(fg),
+						Err(_) => {}
+					}
+				}
+				continue;
+			}
+
+			let cell_x = x + col * cell_width;
+			let cell_y = y + row * cell_height;
+			if let Some(bg) = bg {
+				canvas.fill_rect(cell_x, cell_y, cell_width, cell_height, bg);
+			}
+			canvas.draw_char(cell_x, cell_y, c, fg, scale);
+			col += 1;
+		}
+	}
+}
@@ -0,0 +1,559 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements a graphical user interface, rendered on a framebuffer.
+//!
+//! The interface is navigated with the keyboard only. Keys are read from the terminal.
+//!
+//! Each screen can be drawn without waiting for input, which allows to render it on a
+//! [`fb::MemFramebuffer`].
+
+mod canvas;
+pub mod fb;
+mod font;
+mod logo;
+
+use super::{
+	dialog::{Dialog, MenuItem},
+	input,
+	input::Key,
+};
+use canvas::Canvas;
+use fb::Framebuffer;
+use std::{io, process::exit};
+
+/// The color of the background.
+const COLOR_BACKGROUND: u32 = 0x101018;
+/// The color of the text.
+const COLOR_TEXT: u32 = 0xe0e0e0;
+/// The color of the header, footer and selected items.
+const COLOR_ACCENT: u32 = 0x5f3dc4;
+/// The color of text drawn over [`COLOR_ACCENT`].
+const COLOR_ACCENT_TEXT: u32 = 0xffffff;
+/// The color of borders and input fields.
+const COLOR_BORDER: u32 = 0x606078;
+/// The color of error messages.
+const COLOR_ERROR: u32 = 0xffa500;
+
+/// The graphical user interface.
+pub struct Gui<F: Framebuffer> {
+	/// The framebuffer on which the interface is shown.
+	fb: F,
+	/// The canvas on which screens are drawn before being shown.
+	canvas: Canvas,
+	/// The size of the pixels of glyphs.
+	scale: usize,
+
+	/// The settings of the terminal to restore when closing.
+	///
+	/// If `None`, the terminal is not used.
+	saved_mode: Option<String>,
+
+	/// The lines shown in the progress pane.
+	progress_lines: Vec<String>,
+	/// The overall progress of the installation, in percent.
+	percent: u8,
+}
+
+impl<F: Framebuffer> Gui<F> {
+	/// Creates an interface showing screens on `fb`, without changing the settings of the
+	/// terminal.
+	///
+	/// Keys are still read from the standard input, which allows to drive the interface from a
+	/// script.
+	pub fn headless(fb: F) -> Self {
+		let (width, height) = fb.size();
+		Self {
+			fb,
+			canvas: Canvas::new(width, height),
+			// Keep text readable on large screens
+			scale: (width / 800).min(height / 600).max(1),
+
+			saved_mode: None,
+
+			progress_lines: vec![],
+			percent: 0,
+		}
+	}
+
+	/// Creates an interface showing screens on `fb` and reading keys from the terminal.
+	pub fn new(fb: F) -> io::Result<Self> {
+		let mut gui = Self::headless(fb);
+		gui.saved_mode = Some(input::set_raw_mode()?);
+		Ok(gui)
+	}
+
+	/// Returns the width of a character, in pixels.
+	fn char_width(&self) -> usize {
+		font::WIDTH * self.scale
+	}
+
+	/// Returns the height of a line of text, in pixels.
+	fn line_height(&self) -> usize {
+		(font::HEIGHT + 4) * self.scale
+	}
+
+	/// Returns the position of the top-left corner of the body of screens.
+	fn body_origin(&self) -> (usize, usize) {
+		(self.char_width() * 4, self.line_height() * 3)
+	}
+
+	/// Returns the number of characters fitting on a line of the body of screens.
+	fn text_cols(&self) -> usize {
+		let (width, _) = self.canvas.size();
+		width.saturating_sub(self.body_origin().0 * 2) / self.char_width()
+	}
+
+	/// Returns the first characters of `s`, fitting in `cols` characters.
+	fn fit(s: &str, cols: usize) -> String {
+		s.chars().take(cols).collect()
+	}
+
+	/// Draws an empty screen with the given title and key help.
+	fn frame(&mut self, title: &str, help: &str) {
+		let (width, height) = self.canvas.size();
+		let bar_height = self.line_height() * 2;
+		let text_y = (bar_height - font::HEIGHT * self.scale) / 2;
+		let cols = width / self.char_width();
+
+		self.canvas.fill(COLOR_BACKGROUND);
+		self.canvas.fill_rect(0, 0, width, bar_height, COLOR_ACCENT);
+		let header = Self::fit(
+			&format!("Maestro installer | {title}"),
+			cols.saturating_sub(4),
+		);
+		self.canvas.draw_text(
+			self.char_width() * 2,
+			text_y,
+			&header,
+			COLOR_ACCENT_TEXT,
+			self.scale,
+		);
+
+		let footer_y = height.saturating_sub(bar_height);
+		self.canvas
+			.fill_rect(0, footer_y, width, bar_height, COLOR_ACCENT);
+		self.canvas.draw_text(
+			self.char_width() * 2,
+			footer_y + text_y,
+			&Self::fit(help, cols.saturating_sub(4)),
+			COLOR_ACCENT_TEXT,
+			self.scale,
+		);
+	}
+
+	/// Draws the given lines in a box.
+	///
+	/// Only the last lines are shown if they don't fit.
+	fn text_box(&mut self, y: usize, rows: usize, lines: &[String]) {
+		let (x, _) = self.body_origin();
+		let cols = self.text_cols();
+		let padding = self.char_width();
+		let width = cols * self.char_width();
+		let height = rows * self.line_height() + padding * 2;
+		self.canvas
+			.stroke_rect(x, y, width, height, self.scale, COLOR_BORDER);
+
+		let inner_cols = cols.saturating_sub(2);
+		let skip = lines.len().saturating_sub(rows);
+		for (i, line) in lines.iter().skip(skip).enumerate() {
+			self.canvas.draw_text(
+				x + padding,
+				y + padding + i * self.line_height(),
+				&Self::fit(line, inner_cols),
+				COLOR_TEXT,
+				self.scale,
+			);
+		}
+	}
+
+	/// Shows the drawn screen on the framebuffer.
+	fn present(&mut self) {
+		if let Err(e) = self.fb.present(self.canvas.pixels()) {
+			self.close();
+			eprintln!("Cannot draw on framebuffer: {e}");
+			exit(1);
+		}
+	}
+
+	/// Waits for a key to be pressed and returns it.
+	///
+	/// If reading fails or the user pressed Ctrl-C, the function exits the program.
+	fn read_key(&mut self) -> Key {
+		input::read_key_or_exit(self)
+	}
+
+	/// Draws the welcome screen.
+	pub fn draw_welcome(&mut self) {
+		let (width, height) = self.canvas.size();
+		let (logo_width, logo_height) = logo::size(self.scale);
+		self.canvas.fill(0);
+		logo::draw(
+			&mut self.canvas,
+			width.saturating_sub(logo_width) / 2,
+			height.saturating_sub(logo_height) / 2,
+			self.scale,
+		);
+		self.present();
+	}
+
+	/// Draws a menu, `selected` being the index of the highlighted item.
+	pub fn draw_menu(&mut self, title: &str, text: &str, items: &[MenuItem], selected: usize) {
+		self.frame(title, "Up/Down: move | ENTER: select | ESC: back");
+		let (x, mut y) = self.body_origin();
+		let (_, height) = self.canvas.size();
+		let cols = self.text_cols();
+		let line_height = self.line_height();
+
+		for line in text.lines() {
+			let line = Self::fit(line, cols);
+			self.canvas.draw_text(x, y, &line, COLOR_TEXT, self.scale);
+			y += line_height;
+		}
+		y += line_height;
+
+		// Items, scrolled to keep the selected one visible
+		let details_rows = items.iter().map(|i| i.details.len()).max().unwrap_or(0);
+		let details_height = if details_rows > 0 {
+			(details_rows + 2) * line_height
+		} else {
+			0
+		};
+		let available =
+			(height.saturating_sub(y + details_height + line_height * 3) / line_height).max(1);
+		let offset = (selected + 1).saturating_sub(available);
+		for (i, item) in items.iter().enumerate().skip(offset).take(available) {
+			let label = Self::fit(&item.label, cols.saturating_sub(2));
+			let color = if i == selected {
+				self.canvas
+					.fill_rect(x, y, cols * self.char_width(), line_height, COLOR_ACCENT);
+				COLOR_ACCENT_TEXT
+			} else {
+				COLOR_TEXT
+			};
+			self.canvas.draw_text(
+				x + self.char_width(),
+				y + self.scale * 2,
+				&label,
+				color,
+				self.scale,
+			);
+			y += line_height;
+		}
+
+		// Details of the selected item
+		if details_rows > 0 {
+			let top = height
+				.saturating_sub(details_height + line_height * 2)
+				.max(y + line_height);
+			self.text_box(top, details_rows, &items[selected].details);
+		}
+		self.present();
+	}
+
+	/// Draws a text input field.
+	///
+	/// Arguments:
+	/// - `label` is the text showed next to the input field.
+	/// - `input` is the current content of the field, which is hidden if `hidden` is set.
+	/// - `error` is an error message to show under the field.
+	pub fn draw_input(
+		&mut self,
+		title: &str,
+		label: &str,
+		input: &str,
+		hidden: bool,
+		error: Option<&str>,
+	) {
+		self.frame(title, "ENTER: validate | ESC: back");
+		let (x, y) = self.body_origin();
+		let cols = self.text_cols();
+		let line_height = self.line_height();
+
+		self.canvas
+			.draw_text(x, y, &Self::fit(label, cols), COLOR_TEXT, self.scale);
+
+		let shown = if hidden {
+			"*".repeat(input.chars().count())
+		} else {
+			input.to_owned()
+		};
+		// Keep the end of the input visible, followed by the cursor
+		let field_cols = cols.saturating_sub(3);
+		let skip = shown.chars().count().saturating_sub(field_cols);
+		let shown: String = shown.chars().skip(skip).chain(['_']).collect();
+		let field_y = y + line_height * 2;
+		self.canvas.fill_rect(
+			x,
+			field_y,
+			cols * self.char_width(),
+			line_height,
+			COLOR_BORDER,
+		);
+		self.canvas.draw_text(
+			x + self.char_width(),
+			field_y + self.scale * 2,
+			&shown,
+			COLOR_TEXT,
+			self.scale,
+		);
+
+		if let Some(error) = error {
+			self.canvas.draw_text(
+				x,
+				field_y + line_height * 2,
+				&Self::fit(error, cols),
+				COLOR_ERROR,
+				self.scale,
+			);
+		}
+		self.present();
+	}
+
+	/// Draws a screen showing the given lines of text.
+	pub fn draw_message(&mut self, title: &str, lines: &[String]) {
+		self.frame(title, "ENTER: continue");
+		let (x, y) = self.body_origin();
+		let cols = self.text_cols();
+		for (i, line) in lines.iter().enumerate() {
+			let line = Self::fit(line, cols);
+			self.canvas
+				.draw_text(x, y + i * self.line_height(), &line, COLOR_TEXT, self.scale);
+		}
+		self.present();
+	}
+
+	/// Draws the installation progress pane.
+	pub fn draw_progress(&mut self) {
+		let help = format!("Installing, please wait... {}%", self.percent);
+		self.frame("Installation", &help);
+		let (_, y) = self.body_origin();
+		let (_, height) = self.canvas.size();
+		let rows = height.saturating_sub(y + self.line_height() * 4) / self.line_height();
+		let lines = std::mem::take(&mut self.progress_lines);
+		self.text_box(y, rows, &lines);
+		self.progress_lines = lines;
+		self.present();
+	}
+}
+
+impl<F: Framebuffer> Dialog for Gui<F> {
+	fn welcome(&mut self) {
+		self.draw_welcome();
+		while self.read_key() != Key::Enter {}
+	}
+
+	fn menu(&mut self, title: &str, text: &str, items: &[MenuItem]) -> Option<usize> {
+		let mut selected = 0;
+		loop {
+			self.draw_menu(title, text, items, selected);
+			match self.read_key() {
+				Key::Up => selected = selected.saturating_sub(1),
+				Key::Down => selected = (selected + 1).min(items.len().saturating_sub(1)),
+				// An empty menu can only be left by going back
+				Key::Enter if !items.is_empty() => return Some(selected),
+				Key::Escape => return None,
+				_ => {}
+			}
+		}
+	}
+
+	fn input(
+		&mut self,
+		title: &str,
+		label: &str,
+		hidden: bool,
+		validator: &dyn Fn(&str) -> Result<(), Option<String>>,
+	) -> Option<String> {
+		let mut input = String::new();
+		let mut error: Option<String> = None;
+		loop {
+			self.draw_input(title, label, &input, hidden, error.as_deref());
+			match self.read_key() {
+				Key::Char(c) => {
+					input.push(c);
+				}
+				Key::Backspace => {
+					input.pop();
+				}
+				Key::Escape => return None,
+				Key::Enter => match validator(&input) {
+					Ok(()) => return Some(input),
+					Err(e) => {
+						error = e;
+						input.clear();
+					}
+				},
+				_ => {}
+			}
+		}
+	}
+
+	fn message(&mut self, title: &str, lines: &[String]) {
+		self.draw_message(title, lines);
+		while self.read_key() != Key::Enter {}
+	}
+
+	fn progress(&mut self, line: &str) {
+		self.progress_lines.push(line.to_owned());
+		self.draw_progress();
+	}
+
+	fn set_percent(&mut self, percent: u8) {
+		self.percent = percent;
+		self.draw_progress();
+	}
+
+	fn close(&mut self) {
+		if let Some(saved_mode) = self.saved_mode.take() {
+			let _ = input::restore_mode(&saved_mode);
+		}
+	}
+}
+
+impl<F: Framebuffer> Drop for Gui<F> {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use fb::MemFramebuffer;
+	use std::{env, fs};
+
+	/// The width of the rendered screens.
+	const WIDTH: usize = 320;
+	/// The height of the rendered screens.
+	const HEIGHT: usize = 240;
+
+	/// Creates an interface rendering in memory.
+	fn gui() -> Gui<MemFramebuffer> {
+		Gui::headless(MemFramebuffer::new(WIDTH, HEIGHT, None))
+	}
+
+	/// Returns the pixels of the given PPM image, in the `0xRRGGBB` format.
+	fn pixels(ppm: &[u8]) -> Vec<u32> {
+		let header = format!("P6\n{WIDTH} {HEIGHT}\n255\n");
+		assert!(ppm.starts_with(header.as_bytes()));
+		let data = &ppm[header.len()..];
+		assert_eq!(data.len(), WIDTH * HEIGHT * 3);
+		data.chunks_exact(3)
+			.map(|p| u32::from_be_bytes([0, p[0], p[1], p[2]]))
+			.collect()
+	}
+
+	/// Returns the pixel at the given position on the last presented screen.
+	fn pixel_at(gui: &Gui<MemFramebuffer>, x: usize, y: usize) -> u32 {
+		pixels(&gui.fb.to_ppm())[y * WIDTH + x]
+	}
+
+	#[test]
+	fn welcome() {
+		let mut gui = gui();
+		gui.draw_welcome();
+		let pixels = pixels(&gui.fb.to_ppm());
+		assert_eq!(pixels[0], 0);
+		assert!(pixels.iter().any(|p| *p != 0), "the logo is not drawn");
+	}
+
+	#[test]
+	fn menu() {
+		let mut gui = gui();
+		let items = [
+			MenuItem::new("First"),
+			MenuItem::new("Second"),
+			MenuItem::new("Third"),
+		];
+		gui.draw_menu("Title", "Text", &items, 1);
+		let first = gui.fb.to_ppm();
+		// Header and footer
+		assert_eq!(pixel_at(&gui, 0, 0), COLOR_ACCENT);
+		assert_eq!(pixel_at(&gui, 0, HEIGHT - 1), COLOR_ACCENT);
+		// The selected item is highlighted, the others are not
+		let (x, y) = gui.body_origin();
+		let line_height = gui.line_height();
+		let items_y = y + line_height * 2;
+		assert_eq!(pixel_at(&gui, x, items_y), COLOR_BACKGROUND);
+		assert_eq!(pixel_at(&gui, x, items_y + line_height), COLOR_ACCENT);
+		assert_eq!(
+			pixel_at(&gui, x, items_y + line_height * 2),
+			COLOR_BACKGROUND
+		);
+
+		gui.draw_menu("Title", "Text", &items, 0);
+		assert_ne!(gui.fb.to_ppm(), first);
+		assert_eq!(pixel_at(&gui, x, items_y), COLOR_ACCENT);
+	}
+
+	#[test]
+	fn menu_details() {
+		let mut gui = gui();
+		let mut item = MenuItem::new("Item");
+		item.details = vec!["Details".to_owned()];
+		gui.draw_menu("Title", "Text", &[item], 0);
+		let pixels = pixels(&gui.fb.to_ppm());
+		assert!(
+			pixels.contains(&COLOR_BORDER),
+			"the details box is not drawn"
+		);
+	}
+
+	#[test]
+	fn menu_empty() {
+		let mut gui = gui();
+		gui.draw_menu("Title", "Text", &[], 0);
+		assert_eq!(pixel_at(&gui, 0, 0), COLOR_ACCENT);
+	}
+
+	#[test]
+	fn input() {
+		let mut gui = gui();
+		gui.draw_input("Title", "Label", "secret", false, None);
+		let shown = gui.fb.to_ppm();
+		gui.draw_input("Title", "Label", "secret", true, None);
+		let hidden = gui.fb.to_ppm();
+		assert_ne!(shown, hidden);
+		assert!(!pixels(&hidden).contains(&COLOR_ERROR));
+		// Hidden inputs of the same length are shown the same way
+		gui.draw_input("Title", "Label", "abcdef", true, None);
+		assert_eq!(gui.fb.to_ppm(), hidden);
+	}
+
+	#[test]
+	fn input_error() {
+		let mut gui = gui();
+		gui.draw_input("Title", "Label", "", false, Some("Invalid input"));
+		assert!(pixels(&gui.fb.to_ppm()).contains(&COLOR_ERROR));
+	}
+
+	#[test]
+	fn snapshots() {
+		let dir = env::temp_dir().join(format!("maestro-install-gui-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let mut gui = Gui::headless(MemFramebuffer::new(WIDTH, HEIGHT, Some(dir.clone())));
+		gui.draw_welcome();
+		gui.draw_message("Title", &["Message".to_owned()]);
+		let first = fs::read(dir.join("frame-0000.ppm")).unwrap();
+		let second = fs::read(dir.join("frame-0001.ppm")).unwrap();
+		fs::remove_dir_all(&dir).unwrap();
+		assert_ne!(first, second);
+		assert_eq!(second, gui.fb.to_ppm());
+	}
+}
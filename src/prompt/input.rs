@@ -0,0 +1,188 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements keyboard input from the terminal, key by key.
+//!
+//! The terminal's settings are changed with the `stty` command.
+
+use super::dialog::Dialog;
+use std::{
+	io,
+	io::Read,
+	process::{exit, Command, Stdio},
+	sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+
+/// Tells whether the terminal is in the mode set by [`set_raw_mode`].
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
+/// A key pressed by the user.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Key {
+	Up,
+	Down,
+	Left,
+	Right,
+	Enter,
+	Backspace,
+	Escape,
+	/// Ctrl-C, which does not send a signal in the mode set by [`set_raw_mode`].
+	Interrupt,
+	Char(char),
+	/// A key that is not handled.
+	Unknown,
+}
+
+/// Runs `stty` with the given arguments on the terminal.
+fn stty(args: &[&str]) -> io::Result<()> {
+	let status = Command::new("stty").args(args).status()?;
+	if status.success() {
+		Ok(())
+	} else {
+		Err(io::Error::other("cannot set terminal settings"))
+	}
+}
+
+/// Switches the terminal to a mode where keys are read one by one, without echo.
+///
+/// Ctrl-C is read as [`Key::Interrupt`] instead of killing the program, which would leave the
+/// terminal in this mode.
+///
+/// On success, the function returns the previous settings of the terminal, to be given to
+/// [`restore_mode`].
+pub fn set_raw_mode() -> io::Result<String> {
+	let output = Command::new("stty")
+		.arg("-g")
+		.stdin(Stdio::inherit())
+		.output()?;
+	if !output.status.success() {
+		return Err(io::Error::other("cannot get terminal settings"));
+	}
+	let saved = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+	// Reads return after at most a tenth of a second, which allows to detect a lone escape key
+	stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "1"])?;
+	RAW_MODE.store(true, Relaxed);
+	Ok(saved)
+}
+
+/// Restores the terminal settings returned by [`set_raw_mode`].
+pub fn restore_mode(saved: &str) -> io::Result<()> {
+	stty(&[saved])?;
+	RAW_MODE.store(false, Relaxed);
+	Ok(())
+}
+
+/// Returns the size of the terminal as a number of rows and columns.
+///
+/// If the size cannot be retrieved, the function returns `None`.
+pub fn term_size() -> Option<(usize, usize)> {
+	let output = Command::new("stty")
+		.arg("size")
+		.stdin(Stdio::inherit())
+		.output()
+		.ok()?;
+	let output = String::from_utf8(output.stdout).ok()?;
+	let (rows, cols) = output.trim().split_once(' ')?;
+	let size = (rows.parse().ok()?, cols.parse().ok()?);
+	(size.0 > 0 && size.1 > 0).then_some(size)
+}
+
+/// Reads a byte from `input`.
+///
+/// If no byte is available before the timeout, the function returns `None`.
+fn read_byte<R: Read>(input: &mut R) -> io::Result<Option<u8>> {
+	let mut b = [0];
+	let len = input.read(&mut b)?;
+	Ok((len > 0).then_some(b[0]))
+}
+
+/// Waits for a key to be pressed and returns it.
+///
+/// If the terminal is not in the mode set by [`set_raw_mode`], keys are read as they come from
+/// the standard input, which may be a pipe.
+pub fn read_key() -> io::Result<Key> {
+	let mut stdin = io::stdin().lock();
+
+	let b = loop {
+		match read_byte(&mut stdin)? {
+			Some(b) => break b,
+			// Outside of raw mode, reading nothing means the end of the input has been reached
+			None if !RAW_MODE.load(Relaxed) => return Err(io::ErrorKind::UnexpectedEof.into()),
+			None => {}
+		}
+	};
+	let key = match b {
+		b'\n' | b'\r' => Key::Enter,
+		0x7f | 0x08 => Key::Backspace,
+		0x03 => Key::Interrupt,
+		0x1b => match read_byte(&mut stdin)? {
+			Some(b'[' | b'O') => match read_byte(&mut stdin)? {
+				Some(b'A') => Key::Up,
+				Some(b'B') => Key::Down,
+				Some(b'C') => Key::Right,
+				Some(b'D') => Key::Left,
+				// Skip the rest of sequences such as `\x1b[3~`
+				Some(b'0'..=b'9') => {
+					while let Some(b'0'..=b'9' | b';') = read_byte(&mut stdin)? {}
+					Key::Unknown
+				}
+				_ => Key::Unknown,
+			},
+			_ => Key::Escape,
+		},
+		b if b.is_ascii_control() => Key::Unknown,
+		b => {
+			// Read the remaining bytes of the UTF-8 sequence
+			let len = (b.leading_ones() as usize).max(1);
+			let mut buf = vec![b];
+			for _ in 1..len {
+				let Some(b) = read_byte(&mut stdin)? else {
+					break;
+				};
+				buf.push(b);
+			}
+			String::from_utf8(buf)
+				.ok()
+				.and_then(|s| s.chars().next())
+				.map(Key::Char)
+				.unwrap_or(Key::Unknown)
+		}
+	};
+	Ok(key)
+}
+
+/// Same as [`read_key`], for a user interface made of dialogs reading keys from the terminal.
+///
+/// If reading fails or the user pressed Ctrl-C, the function closes `dialog` to give the terminal
+/// back, then exits the program.
+pub fn read_key_or_exit<D: Dialog + ?Sized>(dialog: &mut D) -> Key {
+	match read_key() {
+		Ok(Key::Interrupt) => {
+			dialog.close();
+			eprintln!("Installation cancelled");
+			exit(130);
+		}
+		Ok(key) => key,
+		Err(e) => {
+			dialog.close();
+			eprintln!("Failed to read key from input: {e}");
+			exit(1);
+		}
+	}
+}
@@ -18,18 +18,60 @@
 
 //! TODO doc
 
+pub mod dialog;
+pub mod gui;
+pub mod input;
 pub mod term;
+pub mod tui;
 
-use crate::install::InstallInfo;
+use crate::install::{progress::Event, InstallInfo, SECTOR_SIZE};
+use common::maestro_utils::util::ByteSize;
+use std::fmt;
+
+/// The number of sectors in a mebibyte.
+pub const MIB_SECTORS: u64 = 1024 * 1024 / SECTOR_SIZE;
+
+/// The kind of swap space created by the automatic partitioning.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum SwapKind {
+	/// A swap partition.
+	Partition,
+	/// A swap file on the root filesystem.
+	File,
+	/// No swap space.
+	None,
+}
+
+impl SwapKind {
+	/// All kinds, in the order they are presented to the user.
+	pub const ALL: [Self; 3] = [Self::Partition, Self::File, Self::None];
+}
+
+impl fmt::Display for SwapKind {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Partition => write!(fmt, "Swap partition"),
+			Self::File => write!(fmt, "Swap file"),
+			Self::None => write!(fmt, "No swap"),
+		}
+	}
+}
 
 /// Enumeration of installation steps.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum InstallStep {
 	Welcome,
 	Localization,
 	SystemInfo,
 	CreateAdmin,
+	Hardware,
 	Partitions,
+	/// Part of the partitioning, without selecting the disk again.
+	///
+	/// This step is reachable from the summary only, to change the bootloader.
+	Bootloader,
+	BootOptions,
+	Summary,
 	Install,
 	Finished,
 }
@@ -42,9 +84,12 @@ impl InstallStep {
 			Self::Localization => 1,
 			Self::SystemInfo => 2,
 			Self::CreateAdmin => 3,
-			Self::Partitions => 4,
-			Self::Install => 5,
-			Self::Finished => 6,
+			Self::Hardware => 4,
+			Self::Partitions | Self::Bootloader => 5,
+			Self::BootOptions => 6,
+			Self::Summary => 7,
+			Self::Install => 8,
+			Self::Finished => 9,
 		}
 	}
 
@@ -55,7 +100,10 @@ impl InstallStep {
 			Self::Localization => Some("Localization"),
 			Self::SystemInfo => Some("System informations"),
 			Self::CreateAdmin => Some("Creating administrator user"),
-			Self::Partitions => Some("Disk partitions"),
+			Self::Hardware => Some("Hardware"),
+			Self::Partitions | Self::Bootloader => Some("Disk partitions"),
+			Self::BootOptions => Some("Boot options"),
+			Self::Summary => Some("Summary"),
 			Self::Install => Some("Installation"),
 			Self::Finished => Some("Finished"),
 		}
@@ -68,12 +116,174 @@ impl InstallStep {
 			Self::Welcome => Some(Self::Localization),
 			Self::Localization => Some(Self::SystemInfo),
 			Self::SystemInfo => Some(Self::CreateAdmin),
-			Self::CreateAdmin => Some(Self::Partitions),
-			Self::Partitions => Some(Self::Install),
+			Self::CreateAdmin => Some(Self::Hardware),
+			Self::Hardware => Some(Self::Partitions),
+			Self::Partitions | Self::Bootloader => Some(Self::BootOptions),
+			Self::BootOptions => Some(Self::Summary),
+			Self::Summary => Some(Self::Install),
 			Self::Install => Some(Self::Finished),
 			Self::Finished => None,
 		}
 	}
+
+	/// Returns the step previous to the current.
+	/// If going back is not possible from this step, the function returns None.
+	pub fn get_previous(&self) -> Option<Self> {
+		match self {
+			Self::Welcome => None,
+			Self::Localization => Some(Self::Welcome),
+			Self::SystemInfo => Some(Self::Localization),
+			Self::CreateAdmin => Some(Self::SystemInfo),
+			Self::Hardware => Some(Self::CreateAdmin),
+			Self::Partitions => Some(Self::Hardware),
+			Self::Bootloader => Some(Self::Partitions),
+			Self::BootOptions => Some(Self::Partitions),
+			Self::Summary => Some(Self::BootOptions),
+			Self::Install => Some(Self::Summary),
+			// The installation cannot be undone
+			Self::Finished => None,
+		}
+	}
+}
+
+/// Keeps track of the current step, allowing to move back and forth between steps.
+pub struct StepCursor {
+	/// The current step.
+	curr_step: Option<InstallStep>,
+	/// Tells whether the current step is being edited from the summary, in which case the
+	/// summary is shown again after it.
+	editing: bool,
+}
+
+impl StepCursor {
+	/// Creates a new instance, starting at the first step.
+	pub fn new() -> Self {
+		Self {
+			curr_step: Some(InstallStep::Welcome),
+			editing: false,
+		}
+	}
+
+	/// Returns the current step.
+	/// If the function returns None, the installation is finished.
+	pub fn get(&self) -> Option<InstallStep> {
+		self.curr_step
+	}
+
+	/// Moves to the step next to the current.
+	pub fn next(&mut self) {
+		if self.editing {
+			self.back_to_summary();
+		} else {
+			self.curr_step = self.curr_step.and_then(|s| s.get_next());
+		}
+	}
+
+	/// Moves to the step previous to the current, if possible.
+	pub fn previous(&mut self) {
+		if self.editing {
+			self.back_to_summary();
+		} else if let Some(prev) = self.curr_step.and_then(|s| s.get_previous()) {
+			self.curr_step = Some(prev);
+		}
+	}
+
+	/// Moves to the given step to edit it. After this step, the summary is shown again.
+	pub fn edit(&mut self, step: InstallStep) {
+		self.curr_step = Some(step);
+		self.editing = true;
+	}
+
+	/// Moves back to the summary after editing a step.
+	fn back_to_summary(&mut self) {
+		self.curr_step = Some(InstallStep::Summary);
+		self.editing = false;
+	}
+}
+
+/// An entry of the summary of collected information.
+pub struct SummaryEntry {
+	/// The step at which the information is prompted.
+	pub step: InstallStep,
+	/// The information.
+	pub text: String,
+	/// Additional lines of details.
+	pub details: Vec<String>,
+}
+
+/// Returns the entries of the summary of the given information.
+pub fn summary(infos: &InstallInfo) -> Vec<SummaryEntry> {
+	let lang = infos
+		.lang
+		.as_ref()
+		.map(ToString::to_string)
+		.unwrap_or_default();
+	vec![
+		SummaryEntry {
+			step: InstallStep::Localization,
+			text: format!("Language: {lang}"),
+			details: vec![],
+		},
+		SummaryEntry {
+			step: InstallStep::SystemInfo,
+			text: format!("Hostname: {}", infos.hostname),
+			details: vec![],
+		},
+		SummaryEntry {
+			step: InstallStep::CreateAdmin,
+			text: format!("Admin user: {}", infos.admin_user),
+			details: vec![],
+		},
+		SummaryEntry {
+			step: InstallStep::Partitions,
+			text: format!("Disk: {}", infos.selected_disk.display()),
+			details: infos
+				.partitions
+				.iter()
+				.map(ToString::to_string)
+				.chain(
+					infos
+						.swap_file_size
+						.map(|size| format!("Swap file: {}", ByteSize(size))),
+				)
+				.collect(),
+		},
+		SummaryEntry {
+			step: InstallStep::Bootloader,
+			text: format!(
+				"Bootloader: {}",
+				infos.bootloader.map(|b| b.to_string()).unwrap_or_default()
+			),
+			details: vec![],
+		},
+		SummaryEntry {
+			step: InstallStep::BootOptions,
+			text: format!("Kernel arguments: {}", infos.kernel_args.join(" ")),
+			details: infos
+				.other_systems
+				.iter()
+				.map(|system| format!("Boot menu entry: {system}"))
+				.collect(),
+		},
+	]
+}
+
+/// Validator for prompts which validates non-empty inputs.
+pub fn non_empty_validator(input: &str) -> Result<(), Option<String>> {
+	if !input.is_empty() {
+		Ok(())
+	} else {
+		Err(None)
+	}
+}
+
+/// Validator for prompts which validates sizes in mebibytes, or empty inputs.
+pub fn size_validator(input: &str) -> Result<(), Option<String>> {
+	if input.is_empty() || input.parse::<u64>().is_ok() {
+		Ok(())
+	} else {
+		Err(Some(format!("Invalid size `{input}`")))
+	}
 }
 
 /// An interface between the installer and the user.
@@ -86,4 +296,71 @@ pub trait InstallPrompt {
 
 	/// Returns prompted information.
 	fn get_infos(&self) -> InstallInfo;
+
+	/// Reports the given installation event to the user.
+	fn progress(&mut self, event: &Event);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn step_cursor_flow() {
+		let mut steps = StepCursor::new();
+		let mut visited = vec![];
+		while let Some(step) = steps.get() {
+			visited.push(step);
+			steps.next();
+		}
+		assert_eq!(
+			visited,
+			[
+				InstallStep::Welcome,
+				InstallStep::Localization,
+				InstallStep::SystemInfo,
+				InstallStep::CreateAdmin,
+				InstallStep::Hardware,
+				InstallStep::Partitions,
+				InstallStep::BootOptions,
+				InstallStep::Summary,
+				InstallStep::Install,
+				InstallStep::Finished,
+			]
+		);
+	}
+
+	#[test]
+	fn step_cursor_previous() {
+		let mut steps = StepCursor::new();
+		// Going back from the first step stays on it
+		steps.previous();
+		assert_eq!(steps.get(), Some(InstallStep::Welcome));
+		steps.next();
+		steps.next();
+		steps.previous();
+		assert_eq!(steps.get(), Some(InstallStep::Localization));
+	}
+
+	#[test]
+	fn step_cursor_edit() {
+		let mut steps = StepCursor::new();
+		while steps.get() != Some(InstallStep::Summary) {
+			steps.next();
+		}
+		// Both completing and cancelling an edited step go back to the summary
+		steps.edit(InstallStep::SystemInfo);
+		assert_eq!(steps.get(), Some(InstallStep::SystemInfo));
+		steps.next();
+		assert_eq!(steps.get(), Some(InstallStep::Summary));
+		steps.edit(InstallStep::Bootloader);
+		assert_eq!(steps.get(), Some(InstallStep::Bootloader));
+		steps.previous();
+		assert_eq!(steps.get(), Some(InstallStep::Summary));
+		// Once back on the summary, steps follow each other again
+		steps.next();
+		assert_eq!(steps.get(), Some(InstallStep::Install));
+		steps.previous();
+		assert_eq!(steps.get(), Some(InstallStep::Summary));
+	}
 }
@@ -16,18 +16,14 @@
  * Maestro. If not, see <https://www.gnu.org/licenses/>.
  */
 
-//! This module implements installation prompt from terminal.
+//! This module implements installation prompt from terminal, line by line.
 
-use super::{InstallPrompt, InstallStep};
-use crate::{
-	install::{InstallInfo, PartitionDesc},
-	lang::Language,
-	util,
-};
-use common::{
-	maestro_utils,
-	maestro_utils::{disk::Disk, util::ByteSize},
+use super::{
+	dialog::{Dialog, MenuItem},
+	non_empty_validator,
 };
+use crate::util;
+use common::maestro_utils;
 use std::process::exit;
 
 /// Resets text style.
@@ -48,6 +44,8 @@ pub const CODE_GREEN: &str = "\x1b[92m";
 ///   function can return an error message which is printed, then the function prompts for input
 ///   again. If no error message is provided, no message is printed and the function prompts for
 ///   input again directly.
+///
+/// If the end of the input is reached, the installation is cancelled and the program exits.
 fn prompt<V: Fn(&str) -> Result<(), Option<String>>>(
 	prompt_text: &str,
 	hidden: bool,
@@ -55,7 +53,10 @@ fn prompt<V: Fn(&str) -> Result<(), Option<String>>>(
 ) -> String {
 	loop {
 		let Some(input) = maestro_utils::prompt::prompt(prompt_text, hidden) else {
-			todo!()
+			// Go to the next line, after the prompt
+			eprintln!();
+			eprintln!("Installation cancelled");
+			exit(1);
 		};
 
 		match validator(&input) {
@@ -66,311 +67,123 @@ fn prompt<V: Fn(&str) -> Result<(), Option<String>>>(
 	}
 }
 
-/// Validator for the `prompt` function which validates non-empty inputs.
-fn non_empty_validator(input: &str) -> Result<(), Option<String>> {
-	if !input.is_empty() {
-		Ok(())
-	} else {
-		Err(None)
-	}
-}
-
-/// Structure representing the terminal prompt.
-pub struct TermPrompt {
-	/// The current step.
-	curr_step: Option<InstallStep>,
+/// The input allowing the user to go back to the previous step.
+const BACK_INPUT: &str = "<";
 
-	/// Install informations.
-	infos: InstallInfo,
+/// Same as [`prompt`], except the user can type [`BACK_INPUT`] to go back to the previous step,
+/// in which case the function returns `None`.
+fn prompt_back<V: Fn(&str) -> Result<(), Option<String>>>(
+	prompt_text: &str,
+	hidden: bool,
+	validator: V,
+) -> Option<String> {
+	let input = prompt(prompt_text, hidden, |input| {
+		if input == BACK_INPUT {
+			Ok(())
+		} else {
+			validator(input)
+		}
+	});
+	(input != BACK_INPUT).then_some(input)
 }
 
-impl TermPrompt {
-	/// Creates a new instance.
-	pub fn new() -> Self {
-		Self {
-			curr_step: Some(InstallStep::Welcome),
-
-			infos: InstallInfo::default(),
+/// Prompts a yes/no question from the user on the terminal.
+///
+/// The function returns `true` if the user answered yes.
+pub fn prompt_yes_no(prompt_text: &str) -> bool {
+	loop {
+		let answer = prompt(prompt_text, false, non_empty_validator);
+		match answer.as_str() {
+			"y" => return true,
+			"n" => return false,
+			_ => {}
 		}
 	}
 }
 
-impl InstallPrompt for TermPrompt {
-	fn get_current_step(&self) -> Option<InstallStep> {
-		self.curr_step
-	}
+/// A user interface made of dialogs printed on the terminal, line by line.
+#[derive(Default)]
+pub struct TermDialog {
+	/// The title of the last dialog, printed again only when it changes.
+	title: String,
+}
 
-	fn next_step(&mut self) {
-		let Some(curr_step) = &self.curr_step else {
+impl TermDialog {
+	/// Prints the given title if it differs from the one of the previous dialog.
+	fn header(&mut self, title: &str) {
+		if title == self.title {
 			return;
-		};
-
-		if let Some(step_name) = curr_step.get_name() {
-			println!("|> Step {}: {step_name}", curr_step.get_number());
+		}
+		self.title = title.to_owned();
+		if !title.is_empty() {
 			println!();
+			println!("|> {title}");
+			println!("(type `{BACK_INPUT}` to go back)");
 		}
+		println!();
+	}
+}
 
-		match curr_step {
-			InstallStep::Welcome => {
-				print!("{}", include_str!("motd"));
-				util::read_line();
-			}
-
-			InstallStep::Localization => {
-				let available_langs = match Language::list() {
-					Ok(l) => l,
-					Err(e) => panic!("Could not read languages list. This is a bug. Error: {e}"),
-				};
-
-				while self.infos.lang.is_none() {
-					println!("Type `?` to get the list of available languages.");
-					let lang = prompt("Type the system's language: ", false, non_empty_validator);
-
-					match lang.as_str() {
-						"?" => {
-							println!("Available languages:");
-							for (_, l) in available_langs.iter() {
-								println!("- {l}");
-							}
-							println!();
-						}
-						_ => {
-							if let Some(lang) = available_langs.get(&lang) {
-								self.infos.lang = Some(lang.clone());
-							} else {
-								eprintln!(
-									"\n{CODE_ORANGE}Invalid language `{lang}`!{CODE_RESET}\n"
-								);
-							}
-						}
-					}
-				}
-
-				// TODO Contient/Country
-				// TODO Timezone
-			}
-
-			InstallStep::SystemInfo => {
-				self.infos.hostname = prompt("Type system hostname: ", false, non_empty_validator);
-			}
-
-			InstallStep::CreateAdmin => {
-				self.infos.admin_user =
-					prompt("Type admin username: ", false, non_empty_validator);
-
-				loop {
-					println!();
-					let pass = prompt("Type admin/root password: ", true, non_empty_validator);
-					let pass_confirm = prompt("Confirm admin/root password: ", true, |_| Ok(()));
-
-					// Check correctness
-					if pass != pass_confirm {
-						eprintln!("{CODE_ORANGE}Passwords don't match!{CODE_RESET}");
-						continue;
-					}
-					let pass = match maestro_utils::user::hash_password(&pass) {
-						Ok(p) => p,
-						Err(e) => {
-							eprintln!("{CODE_ORANGE}Invalid password: {e}{CODE_RESET}");
-							continue;
-						}
-					};
-					self.infos.admin_pass = pass;
-					break;
-				}
-			}
-
-			InstallStep::Partitions => {
-				let disks = Disk::list().unwrap_or_else(|e| {
-					eprintln!("{CODE_RED}Failed to retrieve disks list: {e}{CODE_RESET}");
-					exit(1);
-				});
-				// TODO Filter out disks that don't have enough space
-				if disks.is_empty() {
-					eprintln!(
-						"{CODE_RED}No disk is available for installation. Exiting...{CODE_RESET}"
-					);
-					exit(1);
-				}
-
-				self.infos.selected_disk = loop {
-					println!("Available disks and partitions:");
-					for dev_path in disks.iter() {
-						let Some(disk) = Disk::read(dev_path.clone()).unwrap_or_else(|e| {
-							eprintln!("{CODE_RED}Cannot read disk: {e}{CODE_RESET}");
-							exit(1);
-						}) else {
-							continue;
-						};
-
-						println!(
-							"- {} (sectors: {}, size: {})",
-							dev_path.display(),
-							disk.size(),
-							ByteSize(disk.size()),
-						);
-
-						for p in &disk.partition_table.partitions {
-							println!("\t- {p}");
-						}
-					}
-
-					// If only one disk is available, de facto select it
-					if disks.len() == 1 {
-						break disks.first().unwrap().to_str().unwrap().into();
-					}
-
-					println!();
-					let selected_disk = prompt(
-						"Select the disk to install the system on: ",
-						false,
-						|input| {
-							let exists = disks
-								.iter()
-								.any(|dev_path| dev_path.to_str() == Some(input));
-
-							if input.is_empty() {
-								Ok(())
-							} else if !exists {
-								Err(Some(format!("Disk `{input}` doesn't exist")))
-							} else {
-								Ok(())
-							}
-						},
-					);
-
-					if !selected_disk.is_empty() {
-						break selected_disk.into();
-					}
-				};
-
-				println!();
-				println!(
-					"Installing system on disk `{}`",
-					self.infos.selected_disk.display()
-				);
-				println!("Partitioning options:");
-				println!("1 - Wipe disk and install system automatically (warning: this operation will destroy all data on the disk)");
-				// TODO:
-				//println!("2 - Manual partitioning (advanced)");
-				//println!("3 - Use free space left on disk");
-				// TODO Disable option 3 if not enough free space is left on disk
-				println!();
-				println!("NOTE: Other options are not yet available");
-				println!();
-
-				let option = prompt("Select an option: ", false, |input| match input {
-					"1" => Ok(()),
-					_ => Err(Some(format!("Invalid option `{input}`"))),
-				});
-
-				match option.as_str() {
-					"1" => {
-						let disk_path = disks
-							.into_iter()
-							.find(|dev_path| dev_path == &self.infos.selected_disk)
-							.unwrap();
-						// TODO handle error
-						let disk = Disk::read(disk_path).unwrap().unwrap();
-
-						let bios_boot_part = PartitionDesc {
-							start: 2048,
-							size: 2048,
-
-							// BIOS boot
-							part_type: "21686148-6449-6E6F-744E-656564454649".to_owned(),
-
-							bootable: false,
-
-							mount_path: None,
-						};
-
-						let boot_part = PartitionDesc {
-							start: bios_boot_part.start + bios_boot_part.size,
-							size: 262144,
-
-							// EFI System
-							part_type: "C12A7328-F81F-11D2-BA4B-00A0C93EC93B".to_owned(),
-
-							bootable: true,
-
-							mount_path: Some("/boot".into()),
-						};
-
-						// TODO swap
-
-						let root_start = boot_part.start + boot_part.size;
-						let root_part = PartitionDesc {
-							start: root_start,
-							size: disk.size() - root_start,
-
-							// Linux root (x86)
-							part_type: "44479540-F297-41B2-9AF7-D131D5F0458A".to_owned(),
-
-							bootable: false,
-
-							mount_path: Some("/".into()),
-						};
-
-						self.infos.partitions = vec![
-							bios_boot_part,
-							boot_part,
-							// TODO swap
-							root_part,
-						];
-					}
-
-					"2" => {
-						// TODO Ask for modifications on existing partitions
-						todo!();
-					}
-
-					"3" => {
-						// TODO Build partitions table
-						todo!();
-					}
-
-					_ => unreachable!(),
-				}
-
-				println!();
-				println!("The following partitions will be created:");
-				for p in self.infos.partitions.iter() {
-					println!("- {p}");
-				}
-			}
-
-			InstallStep::Install => {
-				// TODO Add option to export selected options to file
+impl Dialog for TermDialog {
+	fn welcome(&mut self) {
+		print!("{}", include_str!("motd"));
+		util::read_line();
+	}
 
-				loop {
-					let confirm =
-						prompt("Confirm installation? (y/n) ", false, non_empty_validator);
-					match confirm.as_str() {
-						"y" => break,
-						"n" => {
-							eprintln!("{CODE_RED}Installation cancelled{CODE_RESET}");
-							exit(1);
-						}
-						_ => {}
-					}
-				}
+	fn menu(&mut self, title: &str, text: &str, items: &[MenuItem]) -> Option<usize> {
+		self.header(title);
+		println!("{text}");
+		for (i, item) in items.iter().enumerate() {
+			println!("{} - {}", i + 1, item.label);
+			for line in &item.details {
+				println!("\t{line}");
 			}
+		}
+		println!();
+		// A single item is selected by just pressing ENTER
+		let prompt_text = if items.len() == 1 {
+			"Press ENTER to continue: "
+		} else {
+			"Select an option: "
+		};
+		let choice = prompt_back(prompt_text, false, |input| match input.parse::<usize>() {
+			Ok(i) if (1..=items.len()).contains(&i) => Ok(()),
+			_ if input.is_empty() && items.len() == 1 => Ok(()),
+			_ => Err(Some(format!("Invalid option `{input}`"))),
+		})?;
+		println!();
+		Some(choice.parse::<usize>().map(|i| i - 1).unwrap_or(0))
+	}
 
-			InstallStep::Finished => {
-				println!("{CODE_GREEN}Installation is now finished!{CODE_RESET}");
-				println!("To start maestro, unplug your installation medium, then press ENTER");
+	fn input(
+		&mut self,
+		title: &str,
+		label: &str,
+		hidden: bool,
+		validator: &dyn Fn(&str) -> Result<(), Option<String>>,
+	) -> Option<String> {
+		self.header(title);
+		let input = prompt_back(&format!("{label} "), hidden, validator)?;
+		println!();
+		Some(input)
+	}
 
-				util::read_line();
-				util::reboot();
-			}
+	fn message(&mut self, title: &str, lines: &[String]) {
+		self.header(title);
+		for line in lines {
+			println!("{line}");
 		}
+		println!("Press ENTER to continue");
+		util::read_line();
 		println!();
-
-		self.curr_step = curr_step.get_next();
 	}
 
-	fn get_infos(&self) -> InstallInfo {
-		self.infos.clone()
+	fn progress(&mut self, line: &str) {
+		println!("{line}");
 	}
+
+	// The percentage is not shown since each phase is already printed
+	fn set_percent(&mut self, _percent: u8) {}
+
+	fn close(&mut self) {}
 }
@@ -0,0 +1,324 @@
+/*
+ * Copyright 2026 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements a full-screen text user interface, drawn with ANSI escape sequences.
+
+use super::{
+	dialog::{Dialog, MenuItem},
+	input,
+	input::Key,
+	term::{CODE_ORANGE, CODE_RESET},
+};
+use std::{fmt::Write as _, io, io::Write};
+
+/// Clears the screen.
+const CODE_CLEAR: &str = "\x1b[2J\x1b[H";
+/// Hides the cursor.
+const CODE_HIDE_CURSOR: &str = "\x1b[?25l";
+/// Shows the cursor.
+const CODE_SHOW_CURSOR: &str = "\x1b[?25h";
+/// Swaps the background and foreground colors.
+const CODE_REVERSE: &str = "\x1b[7m";
+
+/// The default number of rows, used if the size of the terminal cannot be retrieved.
+const DEFAULT_ROWS: usize = 25;
+/// The default number of columns, used if the size of the terminal cannot be retrieved.
+const DEFAULT_COLS: usize = 80;
+
+/// The first row of the body of screens.
+const BODY_TOP: usize = 3;
+
+/// Returns the escape sequence moving the cursor to the given position, starting at `1`.
+fn goto(row: usize, col: usize) -> String {
+	format!("\x1b[{row};{col}H")
+}
+
+/// Returns the first characters of `s`, fitting in `width` columns.
+fn fit(s: &str, width: usize) -> &str {
+	match s.char_indices().nth(width) {
+		Some((i, _)) => &s[..i],
+		None => s,
+	}
+}
+
+/// The full-screen text user interface.
+pub struct Tui {
+	/// The settings of the terminal to restore when closing.
+	///
+	/// If `None`, the interface is closed.
+	saved_mode: Option<String>,
+
+	/// The number of rows of the terminal.
+	rows: usize,
+	/// The number of columns of the terminal.
+	cols: usize,
+
+	/// The lines shown in the progress pane.
+	progress_lines: Vec<String>,
+	/// The overall progress of the installation, in percent.
+	percent: u8,
+}
+
+impl Tui {
+	/// Creates a new instance, taking control of the terminal.
+	pub fn new() -> io::Result<Self> {
+		let saved_mode = input::set_raw_mode()?;
+		let (rows, cols) = input::term_size().unwrap_or((DEFAULT_ROWS, DEFAULT_COLS));
+		print!("{CODE_HIDE_CURSOR}");
+
+		Ok(Self {
+			saved_mode: Some(saved_mode),
+
+			rows,
+			cols,
+
+			progress_lines: vec![],
+			percent: 0,
+		})
+	}
+
+	/// Draws the installation progress pane.
+	fn draw_progress(&self) {
+		let help = format!("Installing, please wait... {}%", self.percent);
+		let mut out = self.frame("Installation", &help);
+		let height = self.rows.saturating_sub(BODY_TOP + 1);
+		out += &Self::draw_box(BODY_TOP, 3, height, self.text_width(), &self.progress_lines);
+		Self::flush(&out);
+	}
+
+	/// Waits for a key to be pressed and returns it.
+	///
+	/// If reading fails or the user pressed Ctrl-C, the function exits the program.
+	fn read_key(&mut self) -> Key {
+		input::read_key_or_exit(self)
+	}
+
+	/// Returns the number of columns available for text in the body of screens.
+	fn text_width(&self) -> usize {
+		self.cols.saturating_sub(4)
+	}
+
+	/// Returns the escape sequences drawing an empty screen with the given title and key help.
+	fn frame(&self, title: &str, help: &str) -> String {
+		let mut out = CODE_CLEAR.to_owned();
+		let header = format!(" Maestro installer | {title}");
+		let _ = write!(
+			out,
+			"{CODE_REVERSE}{:<width$}{CODE_RESET}",
+			fit(&header, self.cols),
+			width = self.cols
+		);
+		let _ = write!(
+			out,
+			"{}{CODE_REVERSE}{:<width$}{CODE_RESET}",
+			goto(self.rows, 1),
+			fit(&format!(" {help}"), self.cols),
+			width = self.cols
+		);
+		out
+	}
+
+	/// Returns the escape sequences drawing a box.
+	///
+	/// Arguments:
+	/// - `top` and `left` are the position of the top-left corner.
+	/// - `height` and `width` are the outer size of the box.
+	/// - `lines` is the content of the box. Only the last lines are shown if they don't fit.
+	fn draw_box(top: usize, left: usize, height: usize, width: usize, lines: &[String]) -> String {
+		let inner_width = width.saturating_sub(2);
+		let inner_height = height.saturating_sub(2);
+		let border = format!("+{}+", "-".repeat(inner_width));
+
+		let mut out = goto(top, left) + &border;
+		let skip = lines.len().saturating_sub(inner_height);
+		let mut lines = lines.iter().skip(skip);
+		for row in 0..inner_height {
+			let line = lines.next().map(String::as_str).unwrap_or("");
+			let _ = write!(
+				out,
+				"{}|{:<inner_width$}|",
+				goto(top + 1 + row, left),
+				fit(line, inner_width)
+			);
+		}
+		out + &goto((top + height).saturating_sub(1), left) + &border
+	}
+
+	/// Writes the given escape sequences to the terminal.
+	fn flush(out: &str) {
+		let mut stdout = io::stdout().lock();
+		let _ = stdout.write_all(out.as_bytes());
+		let _ = stdout.flush();
+	}
+}
+
+impl Dialog for Tui {
+	fn welcome(&mut self) {
+		Self::flush(&format!("{CODE_CLEAR}{}", include_str!("motd")));
+		while self.read_key() != Key::Enter {}
+	}
+
+	fn menu(&mut self, title: &str, text: &str, items: &[MenuItem]) -> Option<usize> {
+		let width = self.text_width();
+		let details_height = items
+			.iter()
+			.map(|i| i.details.len())
+			.max()
+			.filter(|len| *len > 0)
+			.map(|len| len + 2)
+			.unwrap_or(0);
+
+		let mut selected: usize = 0;
+		loop {
+			let mut out = self.frame(title, "Up/Down: move | ENTER: select | ESC: back");
+			let mut row = BODY_TOP;
+			for line in text.lines() {
+				out += &goto(row, 3);
+				out += fit(line, width);
+				row += 1;
+			}
+			row += 1;
+
+			// Items, scrolled to keep the selected one visible
+			let available = self.rows.saturating_sub(row + details_height + 2).max(1);
+			let offset = (selected + 1).saturating_sub(available);
+			for (i, item) in items.iter().enumerate().skip(offset).take(available) {
+				let label = fit(&item.label, width.saturating_sub(4));
+				if i == selected {
+					let _ = write!(out, "{}{CODE_REVERSE} > {label} {CODE_RESET}", goto(row, 3));
+				} else {
+					let _ = write!(out, "{}   {label}", goto(row, 3));
+				}
+				row += 1;
+			}
+
+			// Details of the selected item
+			if details_height > 0 {
+				let top = self.rows.saturating_sub(details_height + 1).max(row + 1);
+				out += &Self::draw_box(top, 3, details_height, width, &items[selected].details);
+			}
+			Self::flush(&out);
+
+			match self.read_key() {
+				Key::Up => selected = selected.saturating_sub(1),
+				Key::Down => selected = (selected + 1).min(items.len().saturating_sub(1)),
+				// An empty menu can only be left by going back
+				Key::Enter if !items.is_empty() => return Some(selected),
+				Key::Escape => return None,
+				_ => {}
+			}
+		}
+	}
+
+	fn input(
+		&mut self,
+		title: &str,
+		label: &str,
+		hidden: bool,
+		validator: &dyn Fn(&str) -> Result<(), Option<String>>,
+	) -> Option<String> {
+		let width = self.text_width();
+		let mut input = String::new();
+		let mut error: Option<String> = None;
+		loop {
+			let mut out = self.frame(title, "ENTER: validate | ESC: back");
+			out += &goto(BODY_TOP, 3);
+			out += fit(label, width);
+
+			let shown = if hidden {
+				"*".repeat(input.chars().count())
+			} else {
+				input.clone()
+			};
+			// Keep the end of the input visible
+			let skip = shown
+				.chars()
+				.count()
+				.saturating_sub(width.saturating_sub(3));
+			let shown: String = shown.chars().skip(skip).collect();
+			let _ = write!(
+				out,
+				"{}{CODE_REVERSE} {shown:<field_width$}{CODE_RESET}",
+				goto(BODY_TOP + 2, 3),
+				field_width = width.saturating_sub(1)
+			);
+			if let Some(error) = &error {
+				let _ = write!(
+					out,
+					"{}{CODE_ORANGE}{}{CODE_RESET}",
+					goto(BODY_TOP + 4, 3),
+					fit(error, width)
+				);
+			}
+			Self::flush(&out);
+
+			match self.read_key() {
+				Key::Char(c) => {
+					input.push(c);
+				}
+				Key::Backspace => {
+					input.pop();
+				}
+				Key::Escape => return None,
+				Key::Enter => match validator(&input) {
+					Ok(()) => return Some(input),
+					Err(e) => {
+						error = e;
+						input.clear();
+					}
+				},
+				_ => {}
+			}
+		}
+	}
+
+	fn message(&mut self, title: &str, lines: &[String]) {
+		let width = self.text_width();
+		let mut out = self.frame(title, "ENTER: continue");
+		for (i, line) in lines.iter().enumerate() {
+			out += &goto(BODY_TOP + i, 3);
+			out += fit(line, width);
+		}
+		Self::flush(&out);
+		while self.read_key() != Key::Enter {}
+	}
+
+	fn progress(&mut self, line: &str) {
+		self.progress_lines.push(line.to_owned());
+		self.draw_progress();
+	}
+
+	fn set_percent(&mut self, percent: u8) {
+		self.percent = percent;
+		self.draw_progress();
+	}
+
+	fn close(&mut self) {
+		let Some(saved_mode) = self.saved_mode.take() else {
+			return;
+		};
+		Self::flush(&format!("{CODE_RESET}{CODE_CLEAR}{CODE_SHOW_CURSOR}"));
+		let _ = input::restore_mode(&saved_mode);
+	}
+}
+
+impl Drop for Tui {
+	fn drop(&mut self) {
+		self.close();
+	}
+}